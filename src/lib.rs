@@ -0,0 +1,3 @@
+mod runescape;
+
+pub use runescape::*;
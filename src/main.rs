@@ -1,36 +1,734 @@
-mod runescape;
-
 use pathfinding::prelude::dijkstra;
 
 use runescape::gear::GearCache;
 use runescape::gear::GearKind;
+use runescape::gear::item_group::Objective;
+use runescape::gear::passives::active_passives;
+use runescape::gear::sets::worn_sets;
+use runescape::gear::special::{DefenceReduction, SpecialWeapon, FULL_BAR};
+use runescape::gear::WorldType;
+use runescape::gear::rules::{GearRules, Rule, RuleKind};
+use runescape::graph::boosts::Boosts;
+use runescape::graph::constraints::BuildConstraints;
+use runescape::graph::dps::{calculate, DpsReport, Loadout};
+use runescape::graph::level_to_xp;
+use runescape::graph::kill::KillModel;
 use runescape::graph::level::Melee;
+use runescape::graph::planner::Planner;
+use runescape::graph::simulation::simulate_plan;
+use runescape::graph::spots::TrainingSpot;
+use runescape::hiscores::{Hiscores, Skill};
+use runescape::monster::Target;
+use runescape::osrsbox_db::overlay::Overlay;
+use runescape::osrsbox_db::types::{AttackStyle, AttackType};
+use runescape::unlocks::{Profile, UnlockTable};
+use runescape::RunescapeInt;
+
+fn invalid_input(message: String) -> std::io::Error {
+	std::io::Error::new(std::io::ErrorKind::InvalidInput, message)
+}
+
+fn next_value<T, I>(args: &mut I, flag: &str) -> std::io::Result<T>
+	where T: std::str::FromStr, T::Err: std::fmt::Display, I: Iterator<Item=String>
+{
+	let value = args.next().ok_or_else(|| invalid_input(format!("{} needs a value", flag)))?;
+	value.parse().map_err(|e| invalid_input(format!("bad value {} for {}: {}", value, flag, e)))
+}
+
+fn parse_cap(value: &str) -> Option<(&str, RunescapeInt)> {
+	let mut parts = value.splitn(2, '=');
+	let skill = parts.next()?;
+	let level = parts.next()?.parse().ok()?;
+	Some((skill, level))
+}
+
+// the weapon kept in the inventory for its special attack
+fn special_weapon(gear_cache: &GearCache, query: &str) -> std::io::Result<SpecialWeapon> {
+	let item = gear_cache.find(query).map_err(invalid_input)?;
+	if let Some(reason) = gear_cache.lock_reason(item.id) {
+		return Err(invalid_input(reason));
+	}
+	SpecialWeapon::from_item(item).map_err(invalid_input)
+}
+
+// the reductions are thrown together, so the bar has to hold all of them
+fn parse_reductions(value: &str) -> Result<Vec<DefenceReduction>, String> {
+	let reductions: Vec<DefenceReduction> = value.split(',').map(|reduction| reduction.trim().parse()).collect::<Result<_, _>>()?;
+	let energy: f64 = reductions.iter().map(|reduction| reduction.special().energy()).sum();
+	if energy > FULL_BAR {
+		return Err(format!("{} needs {}% special energy, more than a full bar", value, energy));
+	}
+	Ok(reductions)
+}
+
+fn plan<I: Iterator<Item=String>>(mut args: I) -> std::io::Result<()> {
+	let mut start = Melee::new(40, 40, 40, None);
+	let mut world = WorldType::Members;
+	let mut overlay = None;
+	let mut profile = None;
+	let mut unlock_table = UnlockTable::builtin();
+	let mut constraints = BuildConstraints::new();
+	let mut kill_model = KillModel::default();
+	let mut simulation_runs = None;
+	let mut seed = 0;
+	let mut rules = Vec::new();
+	let mut objectives = Objective::DEFAULT.to_vec();
+	let mut boosts = Boosts::default();
+	let mut spec_swap: Option<String> = None;
+
+	let mut target = Target::sand_crab();
+	let mut spots_path: Option<String> = None;
+
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--hiscores" => {
+				let path: String = next_value(&mut args, &arg)?;
+				start = Melee::from_hiscores(&Hiscores::from_path(&path)?);
+			},
+			"--world" => {
+				world = next_value(&mut args, &arg)?;
+			},
+			"--overlay" => {
+				let path: String = next_value(&mut args, &arg)?;
+				overlay = Some(Overlay::from_path(&path)?);
+			},
+			"--profile" => {
+				let path: String = next_value(&mut args, &arg)?;
+				profile = Some(Profile::from_path(&path)?);
+			},
+			"--unlocks" => {
+				let path: String = next_value(&mut args, &arg)?;
+				unlock_table = UnlockTable::from_path(&path)?;
+			},
+			"--build" => {
+				constraints = next_value(&mut args, &arg)?;
+			},
+			"--cap" => {
+				let value: String = next_value(&mut args, &arg)?;
+				let (skill, level) = parse_cap(&value).ok_or_else(|| invalid_input(format!("bad cap {}, expected skill=level", value)))?;
+				constraints = constraints.cap(skill.parse().map_err(invalid_input)?, level);
+			},
+			"--max-combat" => {
+				constraints = constraints.max_combat(next_value(&mut args, &arg)?);
+			},
+			"--respawn" => {
+				kill_model.respawn_seconds = next_value(&mut args, &arg)?;
+			},
+			"--targets" => {
+				kill_model.targets = next_value(&mut args, &arg)?;
+			},
+			"--food-heal" => {
+				kill_model.upkeep.food_heal = next_value(&mut args, &arg)?;
+			},
+			"--food-per-trip" => {
+				kill_model.upkeep.food_per_trip = next_value(&mut args, &arg)?;
+			},
+			"--bank-seconds" => {
+				kill_model.upkeep.bank_seconds = next_value(&mut args, &arg)?;
+			},
+			"--max-damage" => {
+				kill_model.upkeep.max_damage_per_hour = Some(next_value(&mut args, &arg)?);
+			},
+			"--aggression-reset" => {
+				kill_model.aggression_reset_seconds = next_value(&mut args, &arg)?;
+			},
+			// a toml or json file of spots to pick between, or builtin for
+			// data/spots.toml. these replace --target and the kill model flags
+			"--spots" => {
+				spots_path = Some(next_value(&mut args, &arg)?);
+			},
+			"--spec" => {
+				boosts.specials.enabled = true;
+			},
+			"--spec-swap" => {
+				boosts.specials.enabled = true;
+				spec_swap = Some(next_value(&mut args, &arg)?);
+			},
+			"--lightbearer" => {
+				boosts.specials.lightbearer = true;
+			},
+			"--simulate" => {
+				simulation_runs = Some(next_value(&mut args, &arg)?);
+			},
+			"--seed" => {
+				seed = next_value(&mut args, &arg)?;
+			},
+			"--target" => {
+				let previous = std::mem::replace(&mut target, next_value(&mut args, &arg)?);
+				target.on_task = previous.on_task;
+				target.defence_reductions = previous.defence_reductions;
+			},
+			"--on-task" => {
+				target.on_task = true;
+			},
+			// a comma separated list of dwh, bgs, arclight and elder-maul, thrown in order
+			"--reduce" => {
+				let value: String = next_value(&mut args, &arg)?;
+				target.defence_reductions = parse_reductions(&value).map_err(invalid_input)?;
+			},
+			// a comma separated list of attack, strength, defence, prayer and price
+			"--objectives" => {
+				let value: String = next_value(&mut args, &arg)?;
+				objectives = value.split(',').map(|objective| objective.trim().parse()).collect::<Result<_, _>>().map_err(invalid_input)?;
+			},
+			"--include" | "--exclude" | "--pin" => {
+				let kind = match arg.as_str() {
+					"--include" => RuleKind::Include,
+					"--exclude" => RuleKind::Exclude,
+					_           => RuleKind::Pin,
+				};
+				let value: String = next_value(&mut args, &arg)?;
+				rules.push(Rule::parse(kind, &value).map_err(invalid_input)?);
+			},
+			_ => return Err(invalid_input(format!("unknown argument {}", arg))),
+		}
+	}
 
-fn main() -> std::io::Result<()> {
 	println!("building gear cache");
-	let gear_cache = GearCache::new(GearKind::Melee)?;
+	let mut gear_cache = GearCache::new(GearKind::Melee, world, overlay.as_ref())?;
+	if let Some(profile) = &profile {
+		gear_cache = gear_cache.with_unlocks(&unlock_table, profile);
+	}
 	println!("done");
+	for id in gear_cache.locked_ids() {
+		println!("{}", gear_cache.lock_reason(id).unwrap());
+	}
+	if let Some(query) = &spec_swap {
+		boosts.specials.swap = Some(special_weapon(&gear_cache, query)?);
+	}
+	let start = start.with_boosts(boosts);
+
+	let mut spots = match spots_path.as_deref() {
+		None            => vec![TrainingSpot::new(target.clone(), kill_model)],
+		Some("builtin") => TrainingSpot::builtin(kill_model.upkeep),
+		Some(path)      => TrainingSpot::from_path(path, kill_model.upkeep)?,
+	};
+	if spots.is_empty() {
+		return Err(invalid_input("there are no training spots to pick from".into()));
+	}
+	for spot in &mut spots {
+		spot.target.on_task = target.on_task;
+		spot.target.defence_reductions = target.defence_reductions.clone();
+	}
 
 	// let breakpoint = gear_cache.get_breakpoint(70, 70, 60);
 	// dbg!(breakpoint);
 
 	// return Ok(());
 
-	let start = Melee::new(40, 40, 40, None);
-	let goal = Melee::new(70, 70, 70, None);
+	let planner = Planner {
+		gear_cache: &gear_cache,
+		goal: constraints.limit_goal(&start, &Melee::new(70, 70, 70, None)),
+		constraints,
+		spots,
+		rules: GearRules::resolve(&rules, &gear_cache).map_err(invalid_input)?,
+		objectives,
+	};
 
-	let result = dijkstra(&start, |p| p.successors(&gear_cache, &goal), |p| *p == goal);
+	let result = dijkstra(&start, |p| p.successors(&planner), |p| p.same_levels(&planner.goal));
 
 	if let Some((v, h)) = result {
-		for s in v.windows(2) {
-			println!("train from {} to {} wearing:", s[0], s[1]);
-			let got = (&s[1]).gear_that_got_us_here.clone().unwrap();
-			for gear in got {
-				println!("\t{}", gear.group_name(&gear_cache));
+		let simulation = simulation_runs.map(|runs| simulate_plan(&v, &planner, runs, seed));
+
+		for rule in planner.rules.descriptions() {
+			println!("rule: {}", rule);
+		}
+		if !target.defence_reductions.is_empty() {
+			let specials: Vec<&str> = target.defence_reductions.iter().map(|reduction| reduction.special().name()).collect();
+			println!("lowering defence whenever the special bar holds: {}", specials.join(", "));
+		}
+		println!("starting at combat level {}", start.combat_level());
+		for (i, s) in v.windows(2).enumerate() {
+			let (from_combat, to_combat) = (s[0].combat_level(), s[1].combat_level());
+			if from_combat == to_combat {
+				println!("train from {} to {} at combat {} wearing:", s[0], s[1], to_combat);
+			} else {
+				println!("train from {} to {} at combat {} -> {} wearing:", s[0], s[1], from_combat, to_combat);
+			}
+			let spot = planner.spot(&s[1]);
+			if planner.spots.len() > 1 {
+				if i == 0 {
+					println!("\tat {}", spot.name);
+				} else if planner.travel_hours(&s[0], &s[1]) > 0.0 {
+					println!("\tmove to {} (from {}, {:.0}s away)", spot.name, planner.spot(&s[0]).name, spot.travel_seconds);
+				}
+			}
+			let got = s[1].gear_that_got_us_here.clone().unwrap();
+			for gear in &got {
+				let reasons = planner.rules.reasons(gear);
+				if reasons.is_empty() {
+					println!("\t{}", gear.group_name(&gear_cache));
+				} else {
+					println!("\t{} ({})", gear.group_name(&gear_cache), reasons.join(", "));
+				}
+			}
+			let style = s[1].style_that_got_us_here.unwrap();
+			if style == AttackStyle::Controlled {
+				println!("\tcontrolled, a third of the xp each to attack, strength and defence");
+			}
+			for skipped in planner.rules.skipped_pins(&got, &gear_cache, s[0].levels(), style) {
+				println!("\t{}", skipped);
+			}
+			let sets = worn_sets(&got);
+			if !sets.is_empty() {
+				println!("\tset effects: {}", sets.join(", "));
+			}
+			let passives = active_passives(&got, &spot.target);
+			if !passives.is_empty() {
+				println!("\tpassives: {}", passives.join(", "));
 			}
+			if let Some((special, per_hour)) = s[0].specials_per_hour(style, &got, &spot.target, &spot.kill_model) {
+				println!("\tspecial attacks: {:.0} per hour with the {}", per_hour, special.name().to_lowercase());
+			}
+			let kills = s[1].kills_that_got_us_here.unwrap();
+			println!(
+				"\tagainst {}: {:.2} dps, {:.1} attacks ({:.1}s) to kill, {} xp per kill, {:.0} kills per hour",
+				spot.target.name, kills.dps, kills.attacks_to_kill, kills.seconds_to_kill, spot.target.xp_per_kill(), kills.kills_per_hour,
+			);
+			if let Some(share) = kills.reduced_share {
+				println!("\tlowering defence on {:.0}% of kills", share * 100.0);
+			}
+			if let Some(damage) = kills.damage {
+				println!(
+					"\ttaking {:.0} damage per hour, eating {:.1} food per hour, {:.1}% of the time eating or banking",
+					damage.damage_per_hour, damage.food_per_hour, damage.downtime * 100.0,
+				);
+			}
+			if let Some(simulation) = &simulation {
+				let segment = simulation.segments[i];
+				println!(
+					"\tsimulated: {:.2}/{:.2}/{:.2} hours (p10/p50/p90), expected {:.2} hours",
+					segment.simulated.p10, segment.simulated.p50, segment.simulated.p90, segment.analytic,
+				);
+			}
+		}
+		println!("total time: {} hours, finishing at combat level {}", h, v.last().unwrap().combat_level());
+		if let Some(simulation) = &simulation {
+			println!(
+				"simulated total: {:.2}/{:.2}/{:.2} hours (p10/p50/p90), mean {:.2} hours",
+				simulation.total.p10, simulation.total.p50, simulation.total.p90, simulation.total.mean,
+			);
+		}
+	} else {
+		return Err(invalid_input(format!("no plan reaches {} from {} with this build, gear and target", planner.goal, start)));
+	}
+
+	Ok(())
+}
+
+// the inputs shared by everything that evaluates explicit loadouts
+struct Calculator {
+	// imported from the hiscores, if they were
+	player: Option<Melee>,
+	boosts: Boosts,
+	style: AttackStyle,
+	attack_type: AttackType,
+	target: Target,
+	kill_model: KillModel,
+	world: WorldType,
+	overlay: Option<Overlay>,
+	profile: Option<Profile>,
+	unlock_table: UnlockTable,
+	levels: (Option<RunescapeInt>, Option<RunescapeInt>, Option<RunescapeInt>),
+	spec_swap: Option<String>,
+}
+
+impl Calculator {
+	fn new() -> Self {
+		Self {
+			player: None,
+			boosts: Boosts::default(),
+			style: AttackStyle::Accurate,
+			attack_type: AttackType::Slash,
+			target: Target::sand_crab(),
+			kill_model: KillModel::default(),
+			world: WorldType::Members,
+			overlay: None,
+			profile: None,
+			unlock_table: UnlockTable::builtin(),
+			levels: (None, None, None),
+			spec_swap: None,
+		}
+	}
+
+	// returns false for flags it doesn't know
+	fn parse_flag<I: Iterator<Item=String>>(&mut self, arg: &str, args: &mut I) -> std::io::Result<bool> {
+		match arg {
+			"--hiscores" => {
+				let path: String = next_value(args, arg)?;
+				self.player = Some(Melee::from_hiscores(&Hiscores::from_path(&path)?));
+			},
+			"--attack"    => self.levels.0 = Some(next_value(args, arg)?),
+			"--strength"  => self.levels.1 = Some(next_value(args, arg)?),
+			"--defence"   => self.levels.2 = Some(next_value(args, arg)?),
+			"--potion"    => {
+				let potion = next_value(args, arg)?;
+				self.boosts.attack_potion = potion;
+				self.boosts.strength_potion = potion;
+			},
+			"--attack-potion"   => self.boosts.attack_potion = next_value(args, arg)?,
+			"--strength-potion" => self.boosts.strength_potion = next_value(args, arg)?,
+			"--prayer"    => self.boosts.prayer = next_value(args, arg)?,
+			"--hitpoints-lost" => self.boosts.hitpoints_lost = next_value(args, arg)?,
+			"--spec"      => self.boosts.specials.enabled = true,
+			"--spec-swap" => {
+				self.boosts.specials.enabled = true;
+				self.spec_swap = Some(next_value(args, arg)?);
+			},
+			"--lightbearer" => self.boosts.specials.lightbearer = true,
+			"--style"     => self.style = next_value(args, arg)?,
+			"--type"      => self.attack_type = next_value(args, arg)?,
+			"--target"    => {
+				let previous = std::mem::replace(&mut self.target, next_value(args, arg)?);
+				self.target.on_task = previous.on_task;
+				self.target.defence_reductions = previous.defence_reductions;
+			},
+			"--on-task"   => self.target.on_task = true,
+			"--reduce"    => {
+				let value: String = next_value(args, arg)?;
+				self.target.defence_reductions = parse_reductions(&value).map_err(invalid_input)?;
+			},
+			"--respawn"   => self.kill_model.respawn_seconds = next_value(args, arg)?,
+			"--targets"   => self.kill_model.targets = next_value(args, arg)?,
+			"--aggression-reset" => self.kill_model.aggression_reset_seconds = next_value(args, arg)?,
+			"--food-heal"     => self.kill_model.upkeep.food_heal = next_value(args, arg)?,
+			"--food-per-trip" => self.kill_model.upkeep.food_per_trip = next_value(args, arg)?,
+			"--bank-seconds"  => self.kill_model.upkeep.bank_seconds = next_value(args, arg)?,
+			"--world"     => self.world = next_value(args, arg)?,
+			"--overlay"   => {
+				let path: String = next_value(args, arg)?;
+				self.overlay = Some(Overlay::from_path(&path)?);
+			},
+			"--profile"   => {
+				let path: String = next_value(args, arg)?;
+				self.profile = Some(Profile::from_path(&path)?);
+			},
+			"--unlocks"   => {
+				let path: String = next_value(args, arg)?;
+				self.unlock_table = UnlockTable::from_path(&path)?;
+			},
+			_             => return Ok(false),
+		}
+		Ok(true)
+	}
+
+	// explicit levels win over the hiscores, every other skill stays as imported
+	fn unboosted_player(&self) -> Melee {
+		let (attack, strength, defence) = self.levels;
+		match &self.player {
+			Some(player) => [(Skill::Attack, attack), (Skill::Strength, strength), (Skill::Defence, defence)].iter()
+				.fold(player.clone(), |player, (skill, level)| match level {
+					Some(level) => player.with_level(*skill, *level),
+					None        => player,
+				}),
+			None => Melee::new(attack.unwrap_or(1), strength.unwrap_or(1), defence.unwrap_or(1), None),
+		}
+	}
+
+	fn player(&self, gear_cache: &GearCache) -> std::io::Result<Melee> {
+		let mut boosts = self.boosts;
+		if let Some(query) = &self.spec_swap {
+			boosts.specials.swap = Some(special_weapon(gear_cache, query)?);
+		}
+		Ok(self.unboosted_player().with_boosts(boosts))
+	}
+
+	fn gear_cache(&self) -> std::io::Result<GearCache> {
+		let gear_cache = GearCache::new(GearKind::Melee, self.world, self.overlay.as_ref())?;
+		Ok(match &self.profile {
+			Some(profile) => gear_cache.with_unlocks(&self.unlock_table, profile),
+			None          => gear_cache,
+		})
+	}
+
+	fn loadout<'a>(&self, gear_cache: &'a GearCache, queries: &[String]) -> std::io::Result<Loadout<'a>> {
+		let mut items = Vec::with_capacity(queries.len());
+		for query in queries {
+			let item = gear_cache.find(query).map_err(invalid_input)?;
+			if let Some(reason) = gear_cache.lock_reason(item.id) {
+				return Err(invalid_input(reason));
+			}
+			items.push(item);
+		}
+		Loadout::new(items).map_err(invalid_input)
+	}
+
+	fn calculate(&self, player: &Melee, loadout: &Loadout) -> std::io::Result<DpsReport> {
+		calculate(player, self.style, self.attack_type, loadout, &self.target, &self.kill_model).map_err(invalid_input)
+	}
+}
+
+fn dps<I: Iterator<Item=String>>(mut args: I) -> std::io::Result<()> {
+	let mut calculator = Calculator::new();
+	let mut queries: Vec<String> = Vec::new();
+
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--item" => queries.push(next_value(&mut args, &arg)?),
+			_ => if !calculator.parse_flag(&arg, &mut args)? {
+				return Err(invalid_input(format!("unknown argument {}", arg)));
+			},
+		}
+	}
+
+	let gear_cache = calculator.gear_cache()?;
+	let player = calculator.player(&gear_cache)?;
+	let loadout = calculator.loadout(&gear_cache, &queries)?;
+	let report = calculator.calculate(&player, &loadout)?;
+
+	for item in loadout.items() {
+		println!("{:?}: {} ({})", item.equipment.slot, item.name, item.id);
+	}
+	let groups = loadout.item_groups(calculator.attack_type, calculator.style, None);
+	let sets = worn_sets(&groups);
+	if !sets.is_empty() {
+		println!("set effects: {}", sets.join(", "));
+	}
+	let passives = active_passives(&groups, &calculator.target);
+	if !passives.is_empty() {
+		println!("passives: {}", passives.join(", "));
+	}
+	println!("against {}:", calculator.target.name);
+	println!("\tmax hit:          {}", report.max_hit);
+	println!("\tmax attack roll:  {}", report.max_attack_roll);
+	println!("\tmax defence roll: {}", report.max_defence_roll);
+	if let Some((defence, share)) = report.reduced_defence {
+		println!("\treduced defence:  {:.1} on average, on {:.0}% of kills", defence, share * 100.0);
+	}
+	println!("\taccuracy:         {:.2}%", report.accuracy * 100.0);
+	println!("\tattack speed:     {} ticks", report.attack_speed);
+	println!("\tdps:              {:.3}", report.dps);
+	let skills: Vec<String> = report.xp.skills().iter().map(|skill| format!("{:?}", skill).to_lowercase()).collect();
+	println!("\txp per hour:      {:.0} ({})", report.xp_per_hour, skills.join(", "));
+	println!("\ttime to kill:     {:.1}s", report.seconds_to_kill);
+	if let Some((special, per_hour)) = report.specials_per_hour {
+		println!("\tspecial attacks:  {:.0} per hour ({})", per_hour, special.name().to_lowercase());
+	}
+	if let Some(damage) = report.damage {
+		println!("\tdamage per hour:  {:.0}", damage.damage_per_hour);
+		println!("\tfood per hour:    {:.1}", damage.food_per_hour);
+		println!("\tdowntime:         {:.1}%", damage.downtime * 100.0);
+	}
+
+	Ok(())
+}
+
+fn parse_range(value: &str) -> Option<(RunescapeInt, RunescapeInt)> {
+	let mut parts = value.splitn(2, '-');
+	let from = parts.next()?.parse().ok()?;
+	let to = parts.next()?.parse().ok()?;
+	if from < 1 || to > 127 || from > to {
+		return None;
+	}
+	Some((from, to))
+}
+
+fn compare<I: Iterator<Item=String>>(mut args: I) -> std::io::Result<()> {
+	let mut calculator = Calculator::new();
+	let mut loadouts: Vec<Vec<String>> = Vec::new();
+	let mut range = None;
+
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			// a comma separated list of item ids or names
+			"--loadout" => {
+				let value: String = next_value(&mut args, &arg)?;
+				loadouts.push(value.split(',').map(|query| query.trim().to_string()).collect());
+			},
+			"--range" => {
+				let value: String = next_value(&mut args, &arg)?;
+				range = Some(parse_range(&value).ok_or_else(|| invalid_input(format!("bad range {}, expected from-to levels", value)))?);
+			},
+			_ => if !calculator.parse_flag(&arg, &mut args)? {
+				return Err(invalid_input(format!("unknown argument {}", arg)));
+			},
+		}
+	}
+	if loadouts.len() < 2 {
+		return Err(invalid_input("compare needs at least two --loadout lists".into()));
+	}
+
+	let gear_cache = calculator.gear_cache()?;
+	let player = calculator.player(&gear_cache)?;
+	let mut reports = Vec::with_capacity(loadouts.len());
+	for queries in &loadouts {
+		let loadout = calculator.loadout(&gear_cache, queries)?;
+		let report = calculator.calculate(&player, &loadout)?;
+		reports.push((loadout, report));
+	}
+
+	// the xp range is trained with the stats above, they don't grow along the way
+	let xp = range.map(|(from, to)| (level_to_xp(to) - level_to_xp(from)) as f64);
+	let (_, baseline) = reports[0];
+	println!("against {}, compared to loadout 1:", calculator.target.name);
+	for (i, (loadout, report)) in reports.iter().enumerate() {
+		let names = loadout.items().iter().map(|item| item.name.as_str()).collect::<Vec<_>>().join(", ");
+		println!("loadout {}: {}", i + 1, names);
+		println!("\taccuracy:    {:.2}% ({:+.2}%)", report.accuracy * 100.0, (report.accuracy - baseline.accuracy) * 100.0);
+		println!("\tmax hit:     {} ({:+})", report.max_hit, report.max_hit - baseline.max_hit);
+		println!("\tdps:         {:.3} ({:+.3})", report.dps, report.dps - baseline.dps);
+		println!("\txp per hour: {:.0} ({:+.0})", report.xp_per_hour, report.xp_per_hour - baseline.xp_per_hour);
+		if let (Some(xp), Some((from, to))) = (xp, range) {
+			let hours = xp / report.xp_per_hour;
+			let saved = xp / baseline.xp_per_hour - hours;
+			println!("\t{} to {}:    {:.2} hours ({:.2} hours saved)", from, to, hours, saved);
 		}
-		println!("total time: {} hours", h);
 	}
 
 	Ok(())
 }
+
+fn search<I: Iterator<Item=String>>(mut args: I) -> std::io::Result<()> {
+	let mut world = WorldType::Members;
+	let mut overlay = None;
+	let mut limit = 10;
+	let mut words = Vec::new();
+
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--world" => world = next_value(&mut args, &arg)?,
+			"--limit" => limit = next_value(&mut args, &arg)?,
+			"--overlay" => {
+				let path: String = next_value(&mut args, &arg)?;
+				overlay = Some(Overlay::from_path(&path)?);
+			},
+			_         => words.push(arg),
+		}
+	}
+	if words.is_empty() {
+		return Err(invalid_input("search needs an item name".into()));
+	}
+
+	let gear_cache = GearCache::new(GearKind::Melee, world, overlay.as_ref())?;
+	for (item, score) in gear_cache.search(&words.join(" "), limit) {
+		let equipment = &item.equipment;
+		let speed = item.weapon.as_ref().map_or(String::new(), |weapon| format!(", speed {}", weapon.attack_speed));
+		println!(
+			"{:.2} {} ({}): {:?}, stab {:+} slash {:+} crush {:+} str {:+}{}",
+			score, item.name, item.id, equipment.slot,
+			equipment.attack_stab, equipment.attack_slash, equipment.attack_crush, equipment.melee_strength, speed,
+		);
+	}
+
+	Ok(())
+}
+
+fn variants<I: Iterator<Item=String>>(mut args: I) -> std::io::Result<()> {
+	let mut calculator = Calculator::new();
+	let mut words = Vec::new();
+
+	while let Some(arg) = args.next() {
+		if !calculator.parse_flag(&arg, &mut args)? {
+			words.push(arg);
+		}
+	}
+	if words.is_empty() {
+		return Err(invalid_input("variants needs an item id or name".into()));
+	}
+
+	let gear_cache = calculator.gear_cache()?;
+	let player = calculator.player(&gear_cache)?;
+	let query = words.join(" ");
+	let item = gear_cache.find(&query).map_err(invalid_input)?;
+	let canonical_id = gear_cache.canonical_id(item.id).unwrap();
+
+	println!("{} is kept as id {}", item.name, canonical_id);
+	println!("\tfolded ids: {}", gear_cache.variant_ids(canonical_id).iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", "));
+
+	if let Some(reason) = gear_cache.lock_reason(canonical_id) {
+		println!("\t{}", reason);
+	}
+
+	let breakpoint = gear_cache.get_breakpoint(player.levels());
+	match gear_cache.group_of(canonical_id, breakpoint, calculator.attack_type, calculator.style) {
+		Some(group) => {
+			println!("\tgrouped at {:?} {:?} {:?} with:", breakpoint, calculator.style, calculator.attack_type);
+			for id in &group.item_ids {
+				println!("\t\t{} ({})", gear_cache.get_by_id(*id).unwrap().name, id);
+			}
+		},
+		None => println!("\tnot usable at {:?} {:?} {:?}", breakpoint, calculator.style, calculator.attack_type),
+	}
+
+	Ok(())
+}
+
+fn main() -> std::io::Result<()> {
+	let mut args = std::env::args().skip(1).peekable();
+	match args.peek().map(String::as_str) {
+		Some("dps") => {
+			args.next();
+			dps(args)
+		},
+		Some("compare") => {
+			args.next();
+			compare(args)
+		},
+		Some("search") => {
+			args.next();
+			search(args)
+		},
+		Some("variants") => {
+			args.next();
+			variants(args)
+		},
+		Some("plan") => {
+			args.next();
+			plan(args)
+		},
+		_ => plan(args),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::BTreeMap;
+	use runescape::osrsbox_db::types::Item;
+
+	const WEAPONS: &str = include_str!("../tests/fixtures/osrsbox/items-weapon.json");
+	const HISCORES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/hiscores/main.txt");
+
+	fn calculator(args: &[&str]) -> Calculator {
+		let mut calculator = Calculator::new();
+		let mut args = args.iter().map(|arg| arg.to_string());
+		while let Some(arg) = args.next() {
+			assert!(calculator.parse_flag(&arg, &mut args).unwrap(), "{}", arg);
+		}
+		calculator
+	}
+
+	#[test]
+	fn explicit_levels_only_replace_their_own_skill() {
+		let player = calculator(&["--hiscores", HISCORES, "--strength", "70"]).unboosted_player();
+		assert_eq!(player.level(Skill::Strength), 70);
+		assert_eq!(player.xp(Skill::Strength), level_to_xp(70));
+		// everything else is as imported
+		assert_eq!(player.xp(Skill::Attack), 1_683_249);
+		assert_eq!(player.xp(Skill::Defence), 903_122);
+		assert_eq!(player.xp(Skill::Hitpoints), 2_076_315);
+		assert_eq!(player.level(Skill::Prayer), 60);
+		assert_eq!(player.level(Skill::Magic), 76);
+
+		// without hiscores the rest start out at level 1
+		let player = calculator(&["--attack", "60"]).unboosted_player();
+		assert_eq!((player.level(Skill::Attack), player.level(Skill::Strength), player.level(Skill::Defence)), (60, 1, 1));
+	}
+
+	#[test]
+	fn compared_loadouts_by_hand() {
+		let items: BTreeMap<String, Item> = serde_json::from_str(WEAPONS).unwrap();
+		let mut calculator = calculator(&["--hiscores", HISCORES, "--strength", "70", "--style", "aggressive", "--type", "slash"]);
+		calculator.target = Target { defence_level: 50, defence_slash: 20, ..Target::sand_crab() };
+		let player = calculator.unboosted_player();
+		let report = |id: &str| calculator.calculate(&player, &Loadout::new(vec![&items[id]]).unwrap()).unwrap();
+		let (scimitar, sword) = (report("4587"), report("1277"));
+
+		// 81 effective strength with 66 and 5 strength bonus
+		assert_eq!((scimitar.max_hit, sword.max_hit), (16, 9));
+		// 78 attack is 86 effective, against a (50 + 9) * (20 + 64) defence roll
+		assert_eq!((scimitar.max_attack_roll, sword.max_attack_roll), (86 * 131, 86 * 67));
+		assert!((scimitar.accuracy - (1.0 - 4958.0 / (2.0 * 11267.0))).abs() < 1e-9, "{}", scimitar.accuracy);
+		assert!((sword.accuracy - (1.0 - 4958.0 / (2.0 * 5763.0))).abs() < 1e-9, "{}", sword.accuracy);
+	}
+}
@@ -0,0 +1,194 @@
+use crate::runescape::RunescapeInt;
+use crate::runescape::gear::item_group::ItemGroup;
+use crate::runescape::gear::passives::Passive;
+use crate::runescape::gear::sets::*;
+use crate::runescape::gear::special::SpecialAttack;
+use crate::runescape::monster::Target;
+use crate::runescape::osrsbox_db::types::*;
+
+// the multipliers worn gear adds on top of its summed bonuses, from set
+// effects and from passives against the target
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GearBonus {
+	// effective attack and strength levels, void
+	pub effective_level: f64,
+	// the max attack roll and max hit
+	pub accuracy: f64,
+	pub damage: f64,
+	// damage taken from the target, justiciar
+	pub damage_taken: f64,
+}
+
+impl Default for GearBonus {
+	fn default() -> Self {
+		Self {
+			effective_level: 1.0,
+			accuracy: 1.0,
+			damage: 1.0,
+			damage_taken: 1.0,
+		}
+	}
+}
+
+impl GearBonus {
+	// `hitpoints` is the player's level, `hitpoints_lost` how far below it they fight
+	pub fn of(items: &[ItemGroup], attack_type: AttackType, defence_bonus: RunescapeInt, hitpoints: RunescapeInt, hitpoints_lost: RunescapeInt, target: &Target) -> Self {
+		let worn = |piece: SetPiece| items.iter().any(|group| group.set_piece == Some(piece));
+		let all = |pieces: &[SetPiece]| pieces.iter().all(|piece| worn(*piece));
+		let mut bonus = Self::default();
+
+		if all(VOID) {
+			bonus.effective_level = 1.1;
+		}
+		if all(OBSIDIAN) {
+			bonus.accuracy *= 1.1;
+			bonus.damage *= 1.1;
+		}
+		if all(BERSERKER) {
+			bonus.damage *= 1.2;
+		}
+		if all(DHAROKS) {
+			let lost = hitpoints_lost.max(0).min(hitpoints) as f64;
+			bonus.damage *= 1.0 + lost / 100.0 * hitpoints as f64 / 100.0;
+		}
+		// half a percent a piece against crush, two and a half for all three
+		if attack_type == AttackType::Crush {
+			let pieces = INQUISITORS.iter().filter(|piece| worn(**piece)).count();
+			let inquisitors = if pieces == INQUISITORS.len() { 1.025 } else { 1.0 + 0.005 * pieces as f64 };
+			bonus.accuracy *= inquisitors;
+			bonus.damage *= inquisitors;
+		}
+		// every 30 defence against the target's attack blocks another 1%
+		if all(JUSTICIAR) {
+			bonus.damage_taken = 1.0 - (defence_bonus.max(0) as f64 / 3000.0).min(1.0);
+		}
+
+		let passives: Vec<Passive> = items.iter()
+			.filter_map(|group| group.passive)
+			.filter(|passive| passive.applies(target))
+			.collect();
+		for passive in &passives {
+			// the salve amulet takes over from the slayer helm, they don't stack
+			let salve = passives.contains(&Passive::Salve) || passives.contains(&Passive::SalveEnchanted);
+			if *passive == Passive::SlayerHelm && salve {
+				continue;
+			}
+			let (accuracy, damage) = passive.multipliers();
+			bonus.accuracy *= accuracy;
+			bonus.damage *= damage;
+		}
+		bonus
+	}
+	// the same gear doing a special attack
+	pub fn with_special(&self, special: SpecialAttack) -> Self {
+		let (accuracy, damage) = special.multipliers();
+		Self {
+			accuracy: self.accuracy * accuracy,
+			damage: self.damage * damage,
+			..*self
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::runescape::monster::Attribute;
+
+	fn piece(piece: SetPiece) -> ItemGroup {
+		ItemGroup { set_piece: Some(piece), ..ItemGroup::empty_group(AttackType::Slash) }
+	}
+
+	fn passive(passive: Passive) -> ItemGroup {
+		ItemGroup { passive: Some(passive), ..ItemGroup::empty_group(AttackType::Slash) }
+	}
+
+	fn bonus(items: &[ItemGroup], target: &Target) -> GearBonus {
+		GearBonus::of(items, AttackType::Slash, 0, 99, 0, target)
+	}
+
+	fn close(a: f64, b: f64) -> bool {
+		(a - b).abs() < 1e-9
+	}
+
+	#[test]
+	fn void_raises_effective_levels_only_as_a_full_set() {
+		let crab = Target::sand_crab();
+		let void: Vec<ItemGroup> = VOID.iter().map(|p| piece(*p)).collect();
+		assert_eq!(bonus(&void, &crab), GearBonus { effective_level: 1.1, ..GearBonus::default() });
+		assert_eq!(bonus(&void[1..], &crab), GearBonus::default());
+	}
+
+	#[test]
+	fn obsidian_and_berserker_stack() {
+		let crab = Target::sand_crab();
+		let obsidian: Vec<ItemGroup> = OBSIDIAN.iter().map(|p| piece(*p)).collect();
+		let got = bonus(&obsidian, &crab);
+		assert!(close(got.accuracy, 1.1) && close(got.damage, 1.1), "{:?}", got);
+
+		let berserker: Vec<ItemGroup> = BERSERKER.iter().map(|p| piece(*p)).collect();
+		let got = bonus(&berserker, &crab);
+		assert!(close(got.accuracy, 1.0) && close(got.damage, 1.2), "{:?}", got);
+
+		let both: Vec<ItemGroup> = OBSIDIAN_BERSERKER.iter().map(|p| piece(*p)).collect();
+		let got = bonus(&both, &crab);
+		assert!(close(got.accuracy, 1.1) && close(got.damage, 1.32), "{:?}", got);
+	}
+
+	#[test]
+	fn dharoks_hits_harder_the_more_hitpoints_are_lost() {
+		let crab = Target::sand_crab();
+		let dharoks: Vec<ItemGroup> = DHAROKS.iter().map(|p| piece(*p)).collect();
+		let damage = |hitpoints, lost| GearBonus::of(&dharoks, AttackType::Slash, 0, hitpoints, lost, &crab).damage;
+		assert!(close(damage(99, 0), 1.0));
+		// 1 + lost / 100 * max / 100
+		assert!(close(damage(99, 50), 1.495));
+		assert!(close(damage(99, 98), 1.9702));
+		// never more than the whole of them
+		assert!(close(damage(50, 80), 1.25));
+	}
+
+	#[test]
+	fn inquisitors_counts_pieces_against_crush_only() {
+		let crab = Target::sand_crab();
+		let inquisitors: Vec<ItemGroup> = INQUISITORS.iter().map(|p| piece(*p)).collect();
+		let crush = |items: &[ItemGroup]| GearBonus::of(items, AttackType::Crush, 0, 99, 0, &crab);
+		assert!(close(crush(&inquisitors[..1]).damage, 1.005));
+		assert!(close(crush(&inquisitors[..2]).accuracy, 1.01));
+		// the full set is worth more than its pieces
+		assert!(close(crush(&inquisitors).damage, 1.025));
+		assert!(close(crush(&inquisitors).accuracy, 1.025));
+		assert_eq!(bonus(&inquisitors, &crab), GearBonus::default());
+	}
+
+	#[test]
+	fn justiciar_blocks_damage_with_defence() {
+		let crab = Target::sand_crab();
+		let justiciar: Vec<ItemGroup> = JUSTICIAR.iter().map(|p| piece(*p)).collect();
+		let taken = |defence_bonus| GearBonus::of(&justiciar, AttackType::Slash, defence_bonus, 99, 0, &crab).damage_taken;
+		assert!(close(taken(300), 0.9));
+		assert!(close(taken(0), 1.0));
+		assert!(close(taken(-20), 1.0));
+		assert!(close(GearBonus::of(&justiciar[1..], AttackType::Slash, 300, 99, 0, &crab).damage_taken, 1.0));
+	}
+
+	#[test]
+	fn salve_takes_over_from_the_slayer_helm() {
+		let undead_task = Target {
+			attributes: std::iter::once(Attribute::Undead).collect(),
+			on_task: true,
+			..Target::sand_crab()
+		};
+		let helm = passive(Passive::SlayerHelm);
+		let got = bonus(&[helm.clone(), passive(Passive::Salve)], &undead_task);
+		assert!(close(got.accuracy, 7.0 / 6.0) && close(got.damage, 7.0 / 6.0), "{:?}", got);
+		let got = bonus(&[helm.clone(), passive(Passive::SalveEnchanted)], &undead_task);
+		assert!(close(got.accuracy, 1.2) && close(got.damage, 1.2), "{:?}", got);
+
+		// the helm alone still counts on task
+		let task = Target { on_task: true, ..Target::sand_crab() };
+		let got = bonus(&[helm.clone(), passive(Passive::Salve)], &task);
+		assert!(close(got.damage, 7.0 / 6.0), "{:?}", got);
+		assert_eq!(bonus(&[helm], &Target::sand_crab()), GearBonus::default());
+	}
+}
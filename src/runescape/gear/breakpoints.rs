@@ -1,28 +1,80 @@
-use crate::runescape::graph::xp_to_level;
-use crate::runescape::graph::level_to_xp;
 use crate::runescape::osrsbox_db::types::Stats;
 use crate::runescape::RunescapeInt;
 
-pub type Breakpoint = (RunescapeInt, RunescapeInt, RunescapeInt);
+// a level for every skill an item can require. levels below the lowest
+// requirement of a skill are 0
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub struct Breakpoint {
+	pub attack:    RunescapeInt,
+	pub strength:  RunescapeInt,
+	pub defence:   RunescapeInt,
+	pub hitpoints: RunescapeInt,
+	pub prayer:    RunescapeInt,
+	pub ranged:    RunescapeInt,
+	pub magic:     RunescapeInt,
+}
 
 impl From<Breakpoint> for Stats {
 	fn from(breakpoint: Breakpoint) -> Self {
-		let total_xp = level_to_xp(breakpoint.0) + level_to_xp(breakpoint.1) + level_to_xp(breakpoint.2);
-		let hp_xp = total_xp / 3;
-		let hp_level = xp_to_level(hp_xp);
 		Stats {
-			attack:    Some(breakpoint.0),
-			strength:  Some(breakpoint.1),
-			defence:   Some(breakpoint.2),
-			hitpoints: Some(hp_level),
-			prayer:    None,
-			ranged:    None,
-			magic:     None,
+			attack:    Some(breakpoint.attack),
+			strength:  Some(breakpoint.strength),
+			defence:   Some(breakpoint.defence),
+			hitpoints: Some(breakpoint.hitpoints),
+			prayer:    Some(breakpoint.prayer),
+			ranged:    Some(breakpoint.ranged),
+			magic:     Some(breakpoint.magic),
 		}
 	}
 }
 
+// every distinct requirement of every skill, in ascending order
+#[derive(Debug, Clone)]
+pub struct Breakpoints {
+	pub attack:    Vec<RunescapeInt>,
+	pub strength:  Vec<RunescapeInt>,
+	pub defence:   Vec<RunescapeInt>,
+	pub hitpoints: Vec<RunescapeInt>,
+	pub prayer:    Vec<RunescapeInt>,
+	pub ranged:    Vec<RunescapeInt>,
+	pub magic:     Vec<RunescapeInt>,
+}
+
+impl Breakpoints {
+	// the highest breakpoint at or below each level
+	pub fn snap(&self, levels: Breakpoint) -> Breakpoint {
+		Breakpoint {
+			attack:    check_breakpoint(&self.attack, levels.attack),
+			strength:  check_breakpoint(&self.strength, levels.strength),
+			defence:   check_breakpoint(&self.defence, levels.defence),
+			hitpoints: check_breakpoint(&self.hitpoints, levels.hitpoints),
+			prayer:    check_breakpoint(&self.prayer, levels.prayer),
+			ranged:    check_breakpoint(&self.ranged, levels.ranged),
+			magic:     check_breakpoint(&self.magic, levels.magic),
+		}
+	}
+}
+
+fn check_breakpoint(breakpoints: &[RunescapeInt], value: RunescapeInt) -> RunescapeInt {
+	breakpoints.iter()
+		.take_while(|breakpoint| **breakpoint <= value)
+		.last()
+		.copied()
+		.unwrap_or(0)
+}
+
 macro_rules! breakpoints {
+	($iter:expr) => {
+		Breakpoints {
+			attack:    breakpoints!($iter, attack),
+			strength:  breakpoints!($iter, strength),
+			defence:   breakpoints!($iter, defence),
+			hitpoints: breakpoints!($iter, hitpoints),
+			prayer:    breakpoints!($iter, prayer),
+			ranged:    breakpoints!($iter, ranged),
+			magic:     breakpoints!($iter, magic),
+		}
+	};
 	($iter:expr, $prop:ident) => {
 		{
 			let mut inner_vec = Vec::new();
@@ -58,3 +110,50 @@ macro_rules! breakpoints {
 // 		})
 // 	}
 // }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use itertools::Itertools;
+	use crate::runescape::gear::tests::item;
+
+	#[test]
+	fn breakpoints_come_from_every_requirement() {
+		let items = vec![
+			(4587, item(4587, "Dragon scimitar", "weapon", serde_json::json!({"requirements": {"attack": 60}}))),
+			(1333, item(1333, "Rune scimitar", "weapon", serde_json::json!({"requirements": {"attack": 40}}))),
+			(1319, item(1319, "Rune 2h sword", "2h", serde_json::json!({"requirements": {"attack": 40}}))),
+			(4153, item(4153, "Granite maul", "2h", serde_json::json!({"requirements": {"attack": 50, "strength": 50}}))),
+			(10551, item(10551, "Fighter torso", "body", serde_json::json!({"requirements": {"defence": 40}}))),
+			(1725, item(1725, "Amulet of strength", "neck", serde_json::json!({}))),
+			(4089, item(4089, "Mystic hat", "head", serde_json::json!({"requirements": {"defence": 20, "magic": 40}}))),
+			(11665, item(11665, "Void melee helm", "head", serde_json::json!({"requirements": {"attack": 42, "strength": 42, "defence": 42, "hitpoints": 42, "prayer": 22, "ranged": 42, "magic": 42}}))),
+		];
+		let breakpoints = breakpoints!(&items);
+		assert_eq!(breakpoints.attack, vec![40, 42, 50, 60]);
+		assert_eq!(breakpoints.strength, vec![42, 50]);
+		assert_eq!(breakpoints.defence, vec![20, 40, 42]);
+		assert_eq!(breakpoints.hitpoints, vec![42]);
+		assert_eq!(breakpoints.prayer, vec![22]);
+		assert_eq!(breakpoints.ranged, vec![42]);
+		assert_eq!(breakpoints.magic, vec![40, 42]);
+	}
+
+	#[test]
+	fn levels_snap_down_to_a_breakpoint() {
+		let breakpoints = Breakpoints {
+			attack:    vec![40, 60],
+			strength:  vec![50],
+			defence:   vec![1, 45, 70],
+			hitpoints: vec![42],
+			prayer:    vec![22, 70],
+			ranged:    vec![],
+			magic:     vec![40],
+		};
+		let levels = Breakpoint { attack: 59, strength: 50, defence: 99, hitpoints: 41, prayer: 70, ranged: 99, magic: 45 };
+		assert_eq!(breakpoints.snap(levels), Breakpoint { attack: 40, strength: 50, defence: 70, hitpoints: 0, prayer: 70, ranged: 0, magic: 40 });
+		// every level snaps to itself once it's a breakpoint
+		let snapped = breakpoints.snap(levels);
+		assert_eq!(breakpoints.snap(snapped), snapped);
+	}
+}
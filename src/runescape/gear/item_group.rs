@@ -3,6 +3,9 @@ use std::iter::IntoIterator;
 
 use crate::runescape::RunescapeInt;
 use crate::runescape::gear::GearCache;
+use crate::runescape::gear::passives::Passive;
+use crate::runescape::gear::sets::SetPiece;
+use crate::runescape::gear::special::SpecialAttack;
 use crate::runescape::osrsbox_db::types::*;
 
 use itertools::Itertools;
@@ -12,9 +15,65 @@ pub struct ItemGroup {
 	pub item_ids: Vec<RunescapeInt>,
 	pub attack_value: RunescapeInt,
 	pub strength_value: RunescapeInt,
+	// defence against whatever the target attacks with, 0 when it doesn't
+	pub defence_value: RunescapeInt,
+	// the best prayer bonus and cheapest store price of any member, neither
+	// splits a group
+	pub prayer_value: RunescapeInt,
+	pub cost: RunescapeInt,
 	pub attack_type: AttackType,
 	pub attack_style: Option<AttackStyle>,
 	pub attack_speed: Option<RunescapeInt>,
+	// the invisible levels of the weapon's stance, none for anything else
+	pub style_bonus: StyleBonus,
+	// a two-handed weapon, worn with nothing in the shield slot
+	pub two_handed: bool,
+	// set pieces never share a group with anything outside their set, and
+	// passives and special attacks only with the same one
+	pub set_piece: Option<SetPiece>,
+	pub passive: Option<Passive>,
+	pub special: Option<SpecialAttack>,
+}
+
+// what a group can be better at than another, higher is better for all of them
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Objective {
+	Attack,
+	Strength,
+	Defence,
+	Prayer,
+	// cheaper is better
+	Price,
+}
+
+impl Objective {
+	// compared by default, defence is 0 unless the target fights back
+	pub const DEFAULT: [Objective; 3] = [Objective::Attack, Objective::Strength, Objective::Defence];
+
+	pub fn value(self, group: &ItemGroup) -> RunescapeInt {
+		match self {
+			Objective::Attack   => group.attack_value,
+			Objective::Strength => group.strength_value,
+			Objective::Defence  => group.defence_value,
+			Objective::Prayer   => group.prayer_value,
+			Objective::Price    => -group.cost,
+		}
+	}
+}
+
+impl std::str::FromStr for Objective {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"attack"   => Ok(Objective::Attack),
+			"strength" => Ok(Objective::Strength),
+			"defence"  => Ok(Objective::Defence),
+			"prayer"   => Ok(Objective::Prayer),
+			"price"    => Ok(Objective::Price),
+			_          => Err(format!("unknown objective {}, expected attack, strength, defence, prayer or price", s)),
+		}
+	}
 }
 
 #[derive(Debug)]
@@ -22,47 +81,62 @@ struct DecomposedItem {
 	id: RunescapeInt,
 	attack_value: RunescapeInt,
 	strength_value: RunescapeInt,
+	defence_value: RunescapeInt,
+	prayer_value: RunescapeInt,
+	cost: RunescapeInt,
 	attack_type: AttackType,
 	slot: Slot,
 	attack_style: Option<AttackStyle>,
 	attack_speed: Option<RunescapeInt>,
+	style_bonus: StyleBonus,
+	set_piece: Option<SetPiece>,
+	passive: Option<Passive>,
+	special: Option<SpecialAttack>,
+}
+
+type MeleeStatKey = (RunescapeInt, RunescapeInt, RunescapeInt, AttackType, Slot, Option<AttackStyle>, Option<RunescapeInt>, Option<SetPiece>, Option<Passive>, Option<SpecialAttack>, StyleBonus);
+
+fn melee_stat_keyer(item: &DecomposedItem) -> MeleeStatKey {
+	(item.attack_value, item.strength_value, item.defence_value, item.attack_type, item.slot, item.attack_style, item.attack_speed, item.set_piece, item.passive, item.special, item.style_bonus)
 }
 
-fn melee_stat_keyer(item: &DecomposedItem) -> (RunescapeInt, RunescapeInt, AttackType, Slot, Option<AttackStyle>, Option<RunescapeInt>) {
-	(item.attack_value, item.strength_value, item.attack_type, item.slot, item.attack_style, item.attack_speed)
+pub fn defence_bonus(equipment: &Equipment, attack_type: AttackType) -> RunescapeInt {
+	match attack_type {
+		AttackType::Stab  => equipment.defence_stab,
+		AttackType::Slash => equipment.defence_slash,
+		AttackType::Crush => equipment.defence_crush,
+		_                 => 0,
+	}
 }
 
-pub fn group_similar_items<'a, T: IntoIterator<Item=&'a Item>>(iter: T) -> Vec<ItemGroup> {
+// `defend_against` is the target's attack type, items only split into
+// separate groups by defence when the target fights back
+pub fn group_similar_items<'a, T: IntoIterator<Item=&'a Item>>(iter: T, defend_against: Option<AttackType>) -> Vec<ItemGroup> {
 	iter.into_iter()
-		.flat_map(decompose_item)
+		.flat_map(|item| decompose_item(item, defend_against))
 		.filter(|item| {
-			item.attack_value >= 0 && item.strength_value >= 0
-		})
-		.filter(|item| {
-			match item.attack_style {
-				None => true,
-				Some(style) => match style {
-					AttackStyle::Accurate | AttackStyle::Aggressive | AttackStyle::Defensive => true,
-					_ => false,
-				}
-			}
-		})
-		.sorted_by(|a, b| {
-			let a = (a.attack_value, a.strength_value, a.attack_type, a.slot, a.attack_style, a.attack_speed);
-			let b = (b.attack_value, b.strength_value, b.attack_type, b.slot, b.attack_style, b.attack_speed);
-			Ord::cmp(&a, &b)
+			(item.attack_value >= 0 && item.strength_value >= 0) || item.set_piece.is_some() || item.passive.is_some()
 		})
+		.sorted_by(|a, b| Ord::cmp(&melee_stat_keyer(a), &melee_stat_keyer(b)))
 		.group_by(melee_stat_keyer)
 		.into_iter()
 		.map(|(key, group)| {
-			let item_ids = group.into_iter().map(|item| item.id).collect();
+			let group: Vec<DecomposedItem> = group.collect();
 			ItemGroup {
-				item_ids,
+				item_ids: group.iter().map(|item| item.id).collect(),
 				attack_value: key.0,
 				strength_value: key.1,
-				attack_type: key.2,
-				attack_style: key.4,
-				attack_speed: key.5,
+				defence_value: key.2,
+				prayer_value: group.iter().map(|item| item.prayer_value).max().unwrap(),
+				cost: group.iter().map(|item| item.cost).min().unwrap(),
+				attack_type: key.3,
+				attack_style: key.5,
+				attack_speed: key.6,
+				style_bonus: key.10,
+				two_handed: key.4 == Slot::TwoH,
+				set_piece: key.7,
+				passive: key.8,
+				special: key.9,
 			}
 		})
 		.collect()
@@ -74,9 +148,17 @@ impl ItemGroup {
 			item_ids: Vec::new(),
 			attack_value: 0,
 			strength_value: 0,
-			attack_type: attack_type,
+			defence_value: 0,
+			prayer_value: 0,
+			cost: 0,
+			attack_type,
 			attack_style: None,
 			attack_speed: None,
+			style_bonus: StyleBonus::default(),
+			two_handed: false,
+			set_piece: None,
+			passive: None,
+			special: None,
 		}
 	}
 
@@ -95,10 +177,18 @@ impl ItemGroup {
 		})
 	}
 
+	pub fn member_names(&self, gear_cache: &GearCache) -> Vec<String> {
+		self.item_ids.iter()
+			.sorted()
+			.map(|id| gear_cache.get_by_id(*id).unwrap().name.clone())
+			.unique()
+			.collect()
+	}
+
 	pub fn group_name(&self, gear_cache: &GearCache) -> String {
 		match self.group_identifier() {
 			None => "Nothing".into(),
-			Some(id) => format!("{}-like group", gear_cache.get_by_id(id).unwrap().name),
+			Some(_) => self.member_names(gear_cache).join(" / "),
 		}
 	}
 }
@@ -119,90 +209,141 @@ impl ItemGroup {
 // 	}
 // }
 
-fn has_type_and_style(stances: &Vec<Stance>, attack_type: AttackType, attack_style: AttackStyle) -> bool {
-	match stances.iter().find(|stance| stance.attack_type == Some(attack_type) && stance.attack_style == Some(attack_style)) {
-		Some(_) => true,
-		None => false,
-	}
-}
-
-fn decompose_item_by_type_and_style(item: &Item, attack_type: AttackType, attack_style: AttackStyle) -> Option<DecomposedItem> {
-	let id = item.id;
-	let strength_value = item.equipment.melee_strength;
-	let slot = item.equipment.slot;
-	let (attack_speed, valid) = item.weapon.as_ref().map_or((None, None), |weapon| {
-		(Some(weapon.attack_speed), Some(has_type_and_style(&weapon.stances, attack_type, attack_style)))
-	});
-	if let Some(_) = attack_speed {
-		if let Some(false) = valid {
-			return None;
-		}
-	}
-	Some(DecomposedItem {
-		id, strength_value, slot, attack_type,
-		attack_style: match item.equipment.slot {
-			Slot::Weapon => Some(attack_style),
-			_            => None,
-		},
-		attack_speed: attack_speed,
+// `stance` is a weapon's, with the attack speed it fights at
+fn decompose(item: &Item, attack_type: AttackType, stance: Option<(&StanceEffect, RunescapeInt)>, defend_against: Option<AttackType>) -> DecomposedItem {
+	DecomposedItem {
+		id: item.id,
 		attack_value: match attack_type {
-			AttackType::Stab => item.equipment.attack_stab,
+			AttackType::Stab  => item.equipment.attack_stab,
 			AttackType::Slash => item.equipment.attack_slash,
 			AttackType::Crush => item.equipment.attack_crush,
-			_ => return None,
+			_                 => 0,
 		},
-	})
+		strength_value: item.equipment.melee_strength,
+		defence_value: defend_against.map_or(0, |against| defence_bonus(&item.equipment, against)),
+		prayer_value: item.equipment.prayer,
+		cost: item.cost,
+		attack_type,
+		slot: item.equipment.slot,
+		attack_style: stance.map(|(stance, _)| stance.attack_style),
+		attack_speed: stance.map(|(_, speed)| speed),
+		style_bonus: stance.map_or(StyleBonus::default(), |(stance, _)| stance.style_bonus),
+		set_piece: SetPiece::of(&item.name),
+		passive: Passive::of(&item.name),
+		special: SpecialAttack::of(&item.name),
+	}
 }
 
-fn decompose_item(item: &Item) -> Vec<DecomposedItem> {
-	vec![
-		decompose_item_by_type_and_style(item, AttackType::Stab, AttackStyle::Accurate),
-		decompose_item_by_type_and_style(item, AttackType::Slash, AttackStyle::Accurate),
-		decompose_item_by_type_and_style(item, AttackType::Crush, AttackStyle::Accurate),
-		decompose_item_by_type_and_style(item, AttackType::Stab, AttackStyle::Aggressive),
-		decompose_item_by_type_and_style(item, AttackType::Slash, AttackStyle::Aggressive),
-		decompose_item_by_type_and_style(item, AttackType::Crush, AttackStyle::Aggressive),
-		decompose_item_by_type_and_style(item, AttackType::Stab, AttackStyle::Defensive),
-		decompose_item_by_type_and_style(item, AttackType::Slash, AttackStyle::Defensive),
-		decompose_item_by_type_and_style(item, AttackType::Crush, AttackStyle::Defensive),
-	].into_iter().flatten().collect()
+// a weapon splits into one item for every melee stance in its table, with the
+// stance's speed modifier. anything else counts the same for every attack type
+fn decompose_item(item: &Item, defend_against: Option<AttackType>) -> Vec<DecomposedItem> {
+	match &item.weapon {
+		Some(weapon) => weapon.stance_table().iter()
+			.filter_map(|stance| Some((stance.attack_type?, stance)))
+			.unique_by(|(attack_type, stance)| (*attack_type, stance.attack_style))
+			.map(|(attack_type, stance)| {
+				decompose(item, attack_type, Some((stance, weapon.attack_speed + stance.speed)), defend_against)
+			})
+			.collect(),
+		None => [AttackType::Stab, AttackType::Slash, AttackType::Crush].iter()
+			.map(|attack_type| decompose(item, *attack_type, None, defend_against))
+			.collect(),
+	}
 }
 
-pub fn filter_elided_items(items: &Vec<ItemGroup>) -> Vec<ItemGroup> {
-	let mut attack_speeds: BTreeMap<RunescapeInt, Vec<ItemGroup>> = BTreeMap::new();
-	let sorted = items.into_iter().sorted_by(|a, b| {
-		Ord::cmp(&(a.attack_type, a.attack_value, a.strength_value), &(b.attack_type, b.attack_value, b.strength_value))
-	}).rev();
-
-	for item in sorted {
-		let item_speed = match item.attack_speed {
-			Some(attack_speed) => attack_speed,
-			None => 1,
-		};
-
-		let mut added = false;
-		let speed_vec = attack_speeds.entry(item_speed).or_insert_with(|| {
-			added = true;
-			vec![item.clone()]
-		});
-		if !added {
-			for best in &mut speed_vec.into_iter() {
-				if best.attack_value >= item.attack_value && best.strength_value >= item.strength_value {
-					added = true;
-				} else if best.attack_value <= item.attack_value && best.strength_value <= item.strength_value {
-					std::mem::replace(best, item.clone());
-					added = true;
-				}
-			}
-			if !added {
-				speed_vec.push(item.clone());
-			}
+// whether `a` is at least as good as `b` in every objective and better in one
+fn dominates(a: &ItemGroup, b: &ItemGroup, objectives: &[Objective]) -> bool {
+	let mut better = false;
+	for objective in objectives {
+		let (a, b) = (objective.value(a), objective.value(b));
+		if a < b {
+			return false;
 		}
+		better |= a > b;
+	}
+	better
+}
+
+// the pareto frontier of every attack type and speed. groups that tie in every
+// objective are all kept, neither is better than the other
+pub fn filter_elided_items(items: &[ItemGroup], objectives: &[Objective]) -> Vec<ItemGroup> {
+	let mut partitions: BTreeMap<(AttackType, RunescapeInt), Vec<&ItemGroup>> = BTreeMap::new();
+	for item in items {
+		partitions.entry((item.attack_type, item.attack_speed.unwrap_or(1))).or_default().push(item);
 	}
 
 	let mut out = Vec::new();
-	for (_, mut v) in attack_speeds {
-		out.append(&mut v);
+	for (_, partition) in partitions {
+		let frontier = partition.iter()
+			.filter(|item| !partition.iter().any(|other| dominates(other, item, objectives)))
+			.sorted_by(|a, b| {
+				let a: Vec<RunescapeInt> = objectives.iter().map(|objective| objective.value(a)).collect();
+				let b: Vec<RunescapeInt> = objectives.iter().map(|objective| objective.value(b)).collect();
+				Ord::cmp(&b, &a)
+			})
+			.map(|item| (*item).clone());
+		out.extend(frontier);
 	}
 	out
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use rand::{Rng, SeedableRng};
+	use rand::rngs::StdRng;
+
+	const OBJECTIVES: [Objective; 5] = [Objective::Attack, Objective::Strength, Objective::Defence, Objective::Prayer, Objective::Price];
+
+	// small ranges so ties and duplicates come up often
+	fn random_groups(rng: &mut StdRng) -> Vec<ItemGroup> {
+		let count = rng.gen_range(0, 30);
+		(0..count)
+			.map(|id| ItemGroup {
+				item_ids: vec![id],
+				attack_value: rng.gen_range(-2, 5),
+				strength_value: rng.gen_range(-2, 5),
+				defence_value: rng.gen_range(0, 4),
+				prayer_value: rng.gen_range(0, 3),
+				cost: rng.gen_range(0, 4),
+				attack_speed: if rng.gen() { Some(rng.gen_range(4, 6)) } else { None },
+				..ItemGroup::empty_group([AttackType::Stab, AttackType::Slash][rng.gen_range(0, 2)])
+			})
+			.collect()
+	}
+
+	// every non-empty combination of objectives
+	fn objective_sets() -> Vec<Vec<Objective>> {
+		(1..1 << OBJECTIVES.len())
+			.map(|mask: usize| OBJECTIVES.iter().enumerate().filter(|(i, _)| mask & (1 << i) != 0).map(|(_, objective)| *objective).collect())
+			.collect()
+	}
+
+	// written out again rather than calling `dominates`, so the test doesn't
+	// share its mistakes
+	fn beats(a: &ItemGroup, b: &ItemGroup, objectives: &[Objective]) -> bool {
+		a.attack_type == b.attack_type && a.attack_speed.unwrap_or(1) == b.attack_speed.unwrap_or(1) &&
+		objectives.iter().all(|objective| objective.value(a) >= objective.value(b)) &&
+		objectives.iter().any(|objective| objective.value(a) > objective.value(b))
+	}
+
+	#[test]
+	fn frontier_keeps_exactly_the_non_dominated_groups() {
+		let mut rng = StdRng::seed_from_u64(41);
+		let objective_sets = objective_sets();
+		for _ in 0..200 {
+			let groups = random_groups(&mut rng);
+			for objectives in &objective_sets {
+				let kept: Vec<RunescapeInt> = filter_elided_items(&groups, objectives).iter().map(|group| group.item_ids[0]).collect();
+				assert_eq!(kept.iter().unique().count(), kept.len(), "a group was kept twice");
+				for group in &groups {
+					let dominated = groups.iter().any(|other| beats(other, group, objectives));
+					assert_eq!(
+						kept.contains(&group.item_ids[0]), !dominated,
+						"{:?} with {:?} was {}", group, objectives, if dominated { "kept though dominated" } else { "dropped though not dominated" },
+					);
+				}
+			}
+		}
+	}
+}
@@ -2,36 +2,107 @@ use std::rc::Rc;
 use std::cell::RefCell;
 
 use item_group::ItemGroup;
-use breakpoints::Breakpoint;
+use search::SearchIndex;
+use breakpoints::{Breakpoint, Breakpoints};
 use std::collections::BTreeMap;
 
 use crate::runescape::RunescapeInt;
 use crate::runescape::osrsbox_db::types::*;
 use crate::runescape::osrsbox_db;
+use crate::runescape::osrsbox_db::overlay::Overlay;
+use crate::runescape::unlocks::{Profile, Requirement, UnlockTable};
 
 use itertools::Itertools;
 
 
 #[macro_use]
 pub mod breakpoints;
+pub mod bonus;
 pub mod item_group;
+pub mod passives;
+pub mod rules;
+pub mod search;
+pub mod sets;
+pub mod special;
+
+type SlotGroups = BTreeMap<(AttackType, Option<AttackStyle>), Rc<Vec<ItemGroup>>>;
+// slot, breakpoint and the attack type being defended against
+type SlotKey = (Slot, Breakpoint, Option<AttackType>);
 
 pub struct GearCache {
 	gear: BTreeMap<RunescapeInt, Item>,
-	attack_breakpoints: Vec<RunescapeInt>,
-	strength_breakpoints: Vec<RunescapeInt>,
-	defence_breakpoints: Vec<RunescapeInt>,
-	breakpoint_cache: RefCell<BTreeMap<(Slot, Breakpoint), BTreeMap<(AttackType, Option<AttackStyle>), Rc<Vec<ItemGroup>>>>>,
+	canonical_ids: BTreeMap<RunescapeInt, RunescapeInt>,
+	breakpoints: Breakpoints,
+	// items the player hasn't unlocked yet, by canonical id
+	locked: BTreeMap<RunescapeInt, Vec<Requirement>>,
+	search_index: SearchIndex,
+	breakpoint_cache: RefCell<BTreeMap<SlotKey, SlotGroups>>,
+}
+
+// fighting without a weapon is searched like any other weapon. osrsbox ids
+// are never negative
+pub const UNARMED_ID: RunescapeInt = -1;
+
+fn unarmed() -> Item {
+	let stance = |combat_style, attack_style| serde_json::json!({
+		"combat_style": combat_style,
+		"attack_type": "crush",
+		"attack_style": attack_style,
+	});
+	serde_json::from_value(serde_json::json!({
+		"id": UNARMED_ID,
+		"name": "Unarmed",
+		"members": false,
+		"equipable": true,
+		"equipable_by_player": true,
+		"equipable_weapon": true,
+		"equipment": {
+			"attack_stab": 0, "attack_slash": 0, "attack_crush": 0, "attack_magic": 0, "attack_ranged": 0,
+			"defence_stab": 0, "defence_slash": 0, "defence_crush": 0, "defence_magic": 0, "defence_ranged": 0,
+			"melee_strength": 0, "ranged_strength": 0, "magic_damage": 0, "prayer": 0,
+			"slot": "weapon",
+			"requirements": null,
+		},
+		"weapon": {
+			"attack_speed": 4,
+			"weapon_type": "unarmed",
+			"stances": [stance("punch", "accurate"), stance("kick", "aggressive"), stance("block", "defensive")],
+		},
+	})).expect("the unarmed weapon is a valid item")
 }
 
+// every slot osrsbox lists gear under, two-handed weapons included
+const OSRSBOX_SLOTS: [Slot; 12] = [
+	Slot::Ammo, Slot::Body, Slot::Cape, Slot::Feet, Slot::Hands, Slot::Head,
+	Slot::Legs, Slot::Neck, Slot::Ring, Slot::Shield, Slot::Weapon, Slot::TwoH,
+];
+
 pub enum GearKind {
 	Melee,
 	// Ranged,
 	// Magic,
 }
 
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum WorldType {
+	Members,
+	FreeToPlay,
+}
+
+impl std::str::FromStr for WorldType {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"members" | "p2p" => Ok(WorldType::Members),
+			"f2p"             => Ok(WorldType::FreeToPlay),
+			_                 => Err(format!("unknown world type {}, expected members or f2p", s)),
+		}
+	}
+}
+
 impl GearCache {
-	pub fn new(kind: GearKind) -> std::io::Result<Self> {
+	pub fn new(kind: GearKind, world: WorldType, overlay: Option<&Overlay>) -> std::io::Result<Self> {
 		let predicate = match kind {
 			GearKind::Melee => is_melee_gear,
 			// _               => unreachable!(),
@@ -39,57 +110,156 @@ impl GearCache {
 
 		let mut gear = BTreeMap::new();
 
-		gear.append(&mut osrsbox_db::request(Slot::Ammo)?);
-		gear.append(&mut osrsbox_db::request(Slot::Body)?);
-		gear.append(&mut osrsbox_db::request(Slot::Cape)?);
-		gear.append(&mut osrsbox_db::request(Slot::Feet)?);
-		gear.append(&mut osrsbox_db::request(Slot::Hands)?);
-		gear.append(&mut osrsbox_db::request(Slot::Head)?);
-		gear.append(&mut osrsbox_db::request(Slot::Legs)?);
-		gear.append(&mut osrsbox_db::request(Slot::Neck)?);
-		gear.append(&mut osrsbox_db::request(Slot::Ring)?);
-		gear.append(&mut osrsbox_db::request(Slot::Shield)?);
-		gear.append(&mut osrsbox_db::request(Slot::Weapon)?);
-
-		Ok(Self {
-			attack_breakpoints:   breakpoints!(&gear, attack),
-			strength_breakpoints: breakpoints!(&gear, strength),
-			defence_breakpoints:  breakpoints!(&gear, defence),
-			gear: normalize_gear(gear, predicate),
+		for slot in &OSRSBOX_SLOTS {
+			gear.append(&mut osrsbox_db::request(*slot)?);
+		}
+
+		if let Some(overlay) = overlay {
+			overlay.apply(&mut gear)?;
+		}
+
+		Ok(Self::from_gear(gear, predicate, world))
+	}
+
+	pub(crate) fn from_gear(mut gear: BTreeMap<RunescapeInt, Item>, predicate: fn(&(RunescapeInt, Item)) -> bool, world: WorldType) -> Self {
+		// members items have to be gone before breakpoints are extracted, or f2p
+		// plans would stop at levels only members gear cares about
+		if world == WorldType::FreeToPlay {
+			gear = gear.into_iter().filter(is_free_to_play).collect();
+		}
+
+		let breakpoints = breakpoints!(&gear);
+		let (mut gear, mut canonical_ids) = normalize_gear(gear, predicate);
+		gear.insert(UNARMED_ID, unarmed());
+		canonical_ids.insert(UNARMED_ID, UNARMED_ID);
+		let search_index = SearchIndex::new(gear.values());
+
+		Self {
+			gear,
+			canonical_ids,
+			breakpoints,
+			locked: BTreeMap::new(),
+			search_index,
 			breakpoint_cache: RefCell::new(BTreeMap::new()),
-		})
+		}
+	}
+
+	// every item is unlocked until a profile says otherwise
+	pub fn with_unlocks(mut self, table: &UnlockTable, profile: &Profile) -> Self {
+		// an id in the table locks whatever it was folded into
+		self.locked = BTreeMap::new();
+		for (id, canonical_id) in &self.canonical_ids {
+			let item = &self.gear[canonical_id];
+			for requirement in profile.missing(table, *id, &item.name) {
+				let requirements = self.locked.entry(*canonical_id).or_default();
+				if !requirements.contains(&requirement) {
+					requirements.push(requirement);
+				}
+			}
+		}
+		self.breakpoint_cache.borrow_mut().clear();
+		self
+	}
+
+	pub fn locked_by(&self, id: RunescapeInt) -> Option<&[Requirement]> {
+		self.locked.get(&self.canonical_id(id)?).map(Vec::as_slice)
 	}
 
+	// why an item can't be worn, if it can't
+	pub fn lock_reason(&self, id: RunescapeInt) -> Option<String> {
+		let requirements = self.locked_by(id)?;
+		let requirements: Vec<String> = requirements.iter().map(|requirement| requirement.to_string()).collect();
+		Some(format!("{} is locked, it needs {}", self.get_by_id(id)?.name, requirements.join(", ")))
+	}
+
+	pub fn locked_ids(&self) -> impl Iterator<Item=RunescapeInt> + '_ {
+		self.locked.keys().copied()
+	}
+
+	// folded ids find the item they were folded into
 	pub fn get_by_id(&self, id: RunescapeInt) -> Option<&Item> {
-		self.gear.get(&id)
+		self.gear.get(&self.canonical_id(id)?)
+	}
+
+	pub fn canonical_id(&self, id: RunescapeInt) -> Option<RunescapeInt> {
+		self.canonical_ids.get(&id).copied()
+	}
+
+	// every original id that was folded into `canonical_id`, itself included
+	pub fn variant_ids(&self, canonical_id: RunescapeInt) -> Vec<RunescapeInt> {
+		self.canonical_ids.iter()
+			.filter(|(_, canonical)| **canonical == canonical_id)
+			.map(|(id, _)| *id)
+			.collect()
+	}
+
+	// the stat group an item ends up in for one breakpoint, attack type and style
+	pub fn group_of(&self, id: RunescapeInt, breakpoint: Breakpoint, attack_type: AttackType, attack_style: AttackStyle) -> Option<ItemGroup> {
+		let canonical_id = self.canonical_id(id)?;
+		let slot = self.gear[&canonical_id].equipment.slot.worn_in();
+		self.get_by_slot_full(slot, breakpoint, attack_type, attack_style, None)
+			.iter()
+			.find(|group| group.item_ids.contains(&canonical_id))
+			.cloned()
+	}
+
+	// items by name, best matches first
+	pub fn search(&self, query: &str, limit: usize) -> Vec<(&Item, f64)> {
+		self.search_index.search(query, limit)
+			.into_iter()
+			.map(|candidate| (&self.gear[&candidate.id], candidate.score))
+			.collect()
+	}
+
+	// an item id, or the best match for a name as long as it's clearly the best
+	pub fn find(&self, query: &str) -> Result<&Item, String> {
+		if let Ok(id) = query.parse::<RunescapeInt>() {
+			return self.get_by_id(id).ok_or_else(|| format!("no item with id {}", id));
+		}
+		let candidates = self.search(query, 5);
+		match candidates.as_slice() {
+			[] => Err(format!("no item matches {}", query)),
+			[(item, _)] => Ok(item),
+			[(item, score), (_, next_score), ..] if score > next_score => Ok(item),
+			_ => {
+				let names = candidates.iter().map(|(item, _)| format!("{} ({})", item.name, item.id)).collect::<Vec<_>>();
+				Err(format!("{} is ambiguous, it could be any of {}", query, names.join(", ")))
+			},
+		}
 	}
 
 	fn get_by_slot(&self, slot: Slot) -> Vec<&Item> {
 		let mut v = Vec::new();
-		for (_, item) in &self.gear {
-			if item.equipment.slot == slot {
+		for item in self.gear.values() {
+			if item.equipment.slot.worn_in() == slot {
 				v.push(item)
 			}
 		}
 		v
 	}
 
-	pub fn get_breakpoint(&self, attack: RunescapeInt, strength: RunescapeInt, defence: RunescapeInt) -> Breakpoint {
-		let attack = check_breakpoint(&self.attack_breakpoints, attack);
-		let strength = check_breakpoint(&self.strength_breakpoints, strength);
-		let defence = check_breakpoint(&self.defence_breakpoints, defence);
-		(attack, strength, defence)
+	// players with the same breakpoint can wear exactly the same gear
+	pub fn get_breakpoint(&self, levels: Breakpoint) -> Breakpoint {
+		self.breakpoints.snap(levels)
 	}
 
-	pub fn get_by_slot_full(&self, slot: Slot, breakpoint: Breakpoint, attack_type: AttackType, attack_style: AttackStyle) -> Rc<Vec<ItemGroup>> {
+	// `defend_against` keeps apart items that only differ in their defence
+	// against that attack type. the weapon slot has the two-handed weapons too
+	pub fn get_by_slot_full(&self, slot: Slot, breakpoint: Breakpoint, attack_type: AttackType, attack_style: AttackStyle, defend_against: Option<AttackType>) -> Rc<Vec<ItemGroup>> {
 		let attack_style = match slot {
 			Slot::Weapon => Some(attack_style),
 			_            => None,
 		};
-		Rc::clone(self.breakpoint_cache.borrow_mut().entry((slot, breakpoint)).or_insert_with(|| {
-			let slot_gear = self.get_by_slot(slot);
-			let filtered_gear = filter_by_breakpoint(slot_gear, breakpoint);
-			let groups = item_group::group_similar_items(filtered_gear);
+		Rc::clone(self.breakpoint_cache.borrow_mut().entry((slot, breakpoint, defend_against)).or_insert_with(|| {
+			// pure armour is only worth searching against a target that hits it.
+			// weapons are in the cache for their bonuses, or unarmed for having none
+			let slot_gear = self.get_by_slot(slot).into_iter()
+				.filter(|item| {
+					adds_melee_offence(item) || item.weapon.is_some() ||
+					defend_against.is_some_and(|attack_type| item_group::defence_bonus(&item.equipment, attack_type) > 0)
+				});
+			let filtered_gear = filter_by_breakpoint(slot_gear, breakpoint, &self.locked);
+			let groups = item_group::group_similar_items(filtered_gear, defend_against);
 			let mut map = BTreeMap::new();
 			for group in groups {
 				map.entry((group.attack_type, group.attack_style)).or_insert(Vec::new()).push(group);
@@ -103,39 +273,43 @@ impl GearCache {
 	}
 }
 
-fn check_breakpoint(breakpoints: &Vec<RunescapeInt>, value: RunescapeInt) -> RunescapeInt {
-	for pair in breakpoints.windows(2) {
-		if value < pair[1] {
-			return pair[0];
+// items with the same name and equipment are folded into the lowest id. the
+// second map goes from every id that was kept or folded to the id it became
+fn normalize_gear<T: IntoIterator<Item=(RunescapeInt, Item)>, P: FnMut(&(RunescapeInt, Item)) -> bool>(iter: T, predicate: P) -> (BTreeMap<RunescapeInt, Item>, BTreeMap<RunescapeInt, RunescapeInt>) {
+	// equipment can't be ordered, but few items share a name, so every name
+	// keeps a short list of groups with different equipment
+	let mut by_name: BTreeMap<String, Vec<Vec<(RunescapeInt, Item)>>> = BTreeMap::new();
+	for (id, item) in iter.into_iter().filter(predicate) {
+		let groups = by_name.entry(item.name.clone()).or_default();
+		match groups.iter_mut().find(|group| group[0].1.equipment == item.equipment) {
+			Some(group) => group.push((id, item)),
+			None        => groups.push(vec![(id, item)]),
+		}
+	}
+
+	let mut gear = BTreeMap::new();
+	let mut canonical_ids = BTreeMap::new();
+	for group in by_name.into_values().flatten() {
+		let (canonical_id, item) = group.iter().min_by_key(|(id, _)| *id).unwrap().clone();
+		for (id, _) in &group {
+			canonical_ids.insert(*id, canonical_id);
 		}
+		gear.insert(canonical_id, item);
 	}
-	*breakpoints.last().unwrap_or(&1)
+	(gear, canonical_ids)
 }
 
-fn normalize_gear<T: IntoIterator<Item=(RunescapeInt, Item)>, P: FnMut(&(RunescapeInt, Item)) -> bool>(iter: T, predicate: P) -> BTreeMap<RunescapeInt, Item> {
-	iter.into_iter()
-		.filter(predicate)
-		.sorted_by(|(_, a), (_, b)| Ord::cmp(&a.name, &b.name))
-		.group_by(|(_, item)| (item.name.clone(), item.equipment.clone()))
-		.into_iter()
-		.map(|(_, group)| {
-			group.fold(None, |o, (id, item)| {
-				match o {
-					None => Some((id, item)),
-					Some((other_id, other_item)) => {
-						if id < other_id {
-							Some((id, item))
-						} else {
-							Some((other_id, other_item))
-						}
-					}
-				}
-			}).unwrap()
-		})
-		.collect()
+// an item of unknown membership could be members only, so f2p leaves it out
+fn is_free_to_play((_, item): &(RunescapeInt, Item)) -> bool {
+	item.members == Some(false)
 }
 
-fn is_melee_gear((_, item): &(RunescapeInt, Item)) -> bool {
+// set pieces like the void top and passives like the salve amulet have no
+// melee bonuses of their own
+fn adds_melee_offence(item: &Item) -> bool {
+	if sets::SetPiece::of(&item.name).is_some() || passives::Passive::of(&item.name).is_some() {
+		return true
+	}
 	if item.equipment.attack_stab > 0 {
 		return true
 	}
@@ -151,22 +325,220 @@ fn is_melee_gear((_, item): &(RunescapeInt, Item)) -> bool {
 	false
 }
 
-fn filter_by_breakpoint<'a, T: IntoIterator<Item=&'a Item>>(iter: T, breakpoint: Breakpoint) -> Vec<&'a Item> {
+// armour with melee defence and nothing else stays too, for targets that
+// fight back
+fn is_melee_gear((_, item): &(RunescapeInt, Item)) -> bool {
+	let equipment = &item.equipment;
+	adds_melee_offence(item) || equipment.defence_stab > 0 || equipment.defence_slash > 0 || equipment.defence_crush > 0
+}
+
+// items the breakpoint's levels are too low for, and locked items, are left out
+fn filter_by_breakpoint<'a, T: IntoIterator<Item=&'a Item>>(iter: T, breakpoint: Breakpoint, locked: &BTreeMap<RunescapeInt, Vec<Requirement>>) -> Vec<&'a Item> {
 	let stats = breakpoint.into();
 	iter.into_iter()
+		.filter(|item| !locked.contains_key(&item.id))
 		.filter(|item| {
 			match &item.equipment.requirements {
 				Some(requirements) => {
 					requirements.has_requirements(&stats)
 				},
-				None => {
-					match &item.weapon {
-						Some(_) => false,
-						None => true,
-					}
-				}
+				None => true,
 			}
 		})
-		.into_iter()
 		.collect()
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use super::*;
+
+	// an item with no bonuses besides `bonuses`, like {"attack_slash": 38}
+	pub(crate) fn item(id: RunescapeInt, name: &str, slot: &str, bonuses: serde_json::Value) -> Item {
+		let mut equipment = serde_json::json!({
+			"attack_stab": 0, "attack_slash": 0, "attack_crush": 0, "attack_magic": 0, "attack_ranged": 0,
+			"defence_stab": 0, "defence_slash": 0, "defence_crush": 0, "defence_magic": 0, "defence_ranged": 0,
+			"melee_strength": 0, "ranged_strength": 0, "magic_damage": 0, "prayer": 0,
+			"slot": slot,
+			"requirements": null,
+		});
+		for (field, value) in bonuses.as_object().unwrap() {
+			equipment[field] = value.clone();
+		}
+		serde_json::from_value(serde_json::json!({ "id": id, "name": name, "equipment": equipment })).unwrap()
+	}
+
+	pub(crate) fn weapon(id: RunescapeInt, name: &str, slot: &str, bonuses: serde_json::Value, weapon_type: &str) -> Item {
+		let equipment = serde_json::to_value(item(id, name, slot, bonuses).equipment).unwrap();
+		serde_json::from_value(serde_json::json!({
+			"id": id,
+			"name": name,
+			"equipment": equipment,
+			"weapon": { "attack_speed": 7, "weapon_type": weapon_type, "stances": [] },
+		})).unwrap()
+	}
+
+	pub(crate) fn cache(items: Vec<Item>) -> GearCache {
+		GearCache::from_gear(items.into_iter().map(|item| (item.id, item)).collect(), is_melee_gear, WorldType::Members)
+	}
+
+	pub(crate) const MAXED: Breakpoint = Breakpoint { attack: 99, strength: 99, defence: 99, hitpoints: 99, prayer: 99, ranged: 99, magic: 99 };
+
+	#[test]
+	fn world_types_parse() {
+		assert_eq!("members".parse(), Ok(WorldType::Members));
+		assert_eq!("p2p".parse(), Ok(WorldType::Members));
+		assert_eq!("f2p".parse(), Ok(WorldType::FreeToPlay));
+		assert!("pvp".parse::<WorldType>().unwrap_err().contains("pvp"));
+	}
+
+	#[test]
+	fn free_to_play_drops_members_gear_before_breakpoints() {
+		let member = |members, item: Item| Item { members, ..item };
+		let items = [
+			member(Some(true), item(4587, "Dragon scimitar", "weapon", serde_json::json!({"attack_slash": 67, "requirements": {"attack": 60}}))),
+			member(Some(false), item(1333, "Rune scimitar", "weapon", serde_json::json!({"attack_slash": 45, "requirements": {"attack": 40}}))),
+			member(None, item(6523, "Toktz-xil-ak", "weapon", serde_json::json!({"attack_slash": 38, "requirements": {"attack": 50}}))),
+		];
+		let gear_cache = |world| GearCache::from_gear(items.iter().map(|item| (item.id, item.clone())).collect(), is_melee_gear, world);
+
+		let f2p = gear_cache(WorldType::FreeToPlay);
+		assert!(f2p.get_by_id(1333).is_some());
+		assert!(f2p.get_by_id(4587).is_none());
+		// unknown membership could be members only
+		assert!(f2p.get_by_id(6523).is_none());
+		assert_eq!(f2p.get_breakpoint(MAXED).attack, 40);
+
+		let members = gear_cache(WorldType::Members);
+		assert!(members.get_by_id(4587).is_some());
+		assert!(members.get_by_id(6523).is_some());
+		assert_eq!(members.get_breakpoint(MAXED).attack, 60);
+	}
+
+	#[test]
+	fn weapons_without_requirements_are_kept_at_level_one() {
+		let gear_cache = cache(vec![
+			weapon(4587, "Dragon scimitar", "weapon", serde_json::json!({"attack_slash": 67, "requirements": {"attack": 60}}), "slash_sword"),
+			weapon(1277, "Bronze sword", "weapon", serde_json::json!({"attack_stab": 4, "requirements": null}), "stab_sword"),
+		]);
+		let level_one = gear_cache.get_breakpoint(Breakpoint { attack: 1, strength: 1, defence: 1, hitpoints: 10, prayer: 1, ranged: 1, magic: 1 });
+		let ids = |attack_type| -> Vec<RunescapeInt> {
+			gear_cache.get_by_slot_full(Slot::Weapon, level_one, attack_type, AttackStyle::Accurate, None).iter()
+				.flat_map(|group| group.item_ids.clone())
+				.collect()
+		};
+		assert_eq!(ids(AttackType::Stab), vec![1277]);
+		assert!(ids(AttackType::Slash).is_empty());
+		// punching is always there
+		assert_eq!(ids(AttackType::Crush), vec![UNARMED_ID]);
+		assert!(filter_by_breakpoint(gear_cache.gear.values(), level_one, &BTreeMap::new()).iter().any(|item| item.id == 1277));
+	}
+
+	#[test]
+	fn same_equipment_folds_even_when_not_adjacent() {
+		let items = vec![
+			(30, item(30, "Rune sword", "weapon", serde_json::json!({"attack_slash": 38}))),
+			(10, item(10, "Rune sword", "weapon", serde_json::json!({"attack_slash": 38}))),
+			(20, item(20, "Rune sword", "weapon", serde_json::json!({"attack_slash": 40}))),
+			(5,  item(5,  "Adamant sword", "weapon", serde_json::json!({"attack_slash": 29}))),
+		];
+		let (gear, canonical_ids) = normalize_gear(items, |_| true);
+		assert_eq!(gear.keys().copied().collect::<Vec<_>>(), vec![5, 10, 20]);
+		assert_eq!(canonical_ids[&30], 10);
+		assert_eq!(canonical_ids[&10], 10);
+		assert_eq!(canonical_ids[&20], 20);
+		assert_eq!(canonical_ids[&5], 5);
+	}
+
+	#[test]
+	fn unlocks_cover_every_variant_of_a_name() {
+		let gear_cache = cache(vec![
+			item(1215, "Dragon dagger", "weapon", serde_json::json!({"attack_stab": 40, "melee_strength": 40})),
+			item(5698, "Dragon dagger(p++)", "weapon", serde_json::json!({"attack_stab": 40, "melee_strength": 40})),
+			item(1205, "Bronze dagger", "weapon", serde_json::json!({"attack_stab": 4, "melee_strength": 3})),
+		]);
+		let profile = Profile::default();
+		let gear_cache = gear_cache.with_unlocks(&UnlockTable::builtin(), &profile);
+		assert_eq!(gear_cache.locked_by(5698), Some(&[Requirement::Quest("Lost City".into())][..]));
+		assert!(gear_cache.locked_by(1215).is_some());
+		assert!(gear_cache.locked_by(1205).is_none());
+
+		let profile = Profile { quests: std::iter::once("lost city".to_string()).collect(), ..Profile::default() };
+		let gear_cache = gear_cache.with_unlocks(&UnlockTable::builtin(), &profile);
+		assert_eq!(gear_cache.locked_ids().count(), 0);
+	}
+
+	#[test]
+	fn locked_items_are_filtered_with_a_reason() {
+		let gear_cache = cache(vec![
+			weapon(1215, "Dragon dagger", "weapon", serde_json::json!({"attack_stab": 40, "melee_strength": 40}), "stab_sword"),
+			weapon(1205, "Bronze dagger", "weapon", serde_json::json!({"attack_stab": 4, "melee_strength": 3}), "stab_sword"),
+		]).with_unlocks(&UnlockTable::builtin(), &Profile::default());
+		let ids: Vec<RunescapeInt> = gear_cache.get_by_slot_full(Slot::Weapon, MAXED, AttackType::Stab, AttackStyle::Accurate, None).iter()
+			.flat_map(|group| group.item_ids.clone())
+			.collect();
+		assert!(ids.contains(&1205));
+		assert!(!ids.contains(&1215));
+		assert_eq!(gear_cache.lock_reason(1215).as_deref(), Some("Dragon dagger is locked, it needs quest Lost City"));
+		assert_eq!(gear_cache.lock_reason(1205), None);
+	}
+
+	#[test]
+	fn two_handed_weapons_are_searched_in_the_weapon_slot() {
+		let gear_cache = cache(vec![
+			weapon(4718, "Dharok's greataxe", "2h", serde_json::json!({"attack_slash": 103, "attack_crush": 95, "melee_strength": 105}), "axe"),
+			weapon(1333, "Rune scimitar", "weapon", serde_json::json!({"attack_slash": 45, "melee_strength": 44}), "slash_sword"),
+			item(4716, "Dharok's helm", "head", serde_json::json!({"melee_strength": 0, "defence_slash": 45})),
+			item(4720, "Dharok's platebody", "body", serde_json::json!({"defence_slash": 120})),
+			item(4722, "Dharok's platelegs", "legs", serde_json::json!({"defence_slash": 88})),
+		]);
+		let weapons = gear_cache.get_by_slot_full(Slot::Weapon, MAXED, AttackType::Slash, AttackStyle::Aggressive, None);
+		let greataxe = weapons.iter().find(|group| group.item_ids == vec![4718]).expect("the greataxe is searched");
+		assert!(greataxe.two_handed);
+		assert!(weapons.iter().any(|group| group.item_ids == vec![1333] && !group.two_handed));
+
+		let slots = [Slot::Weapon, Slot::Head, Slot::Body, Slot::Legs, Slot::Shield];
+		let allowed: Vec<Vec<ItemGroup>> = slots.iter()
+			.map(|slot| gear_cache.get_by_slot_full(*slot, MAXED, AttackType::Slash, AttackStyle::Aggressive, Some(AttackType::Slash)).to_vec())
+			.collect();
+		let search = sets::set_search(sets::DHAROKS, &slots, &allowed, &allowed).expect("every dharok's piece can be worn");
+		assert_eq!(search[0].len(), 1);
+		assert!(search[0][0].two_handed);
+	}
+
+	#[test]
+	fn tank_armour_is_only_searched_against_targets_that_fight_back() {
+		let gear_cache = cache(vec![
+			item(1127, "Rune platebody", "body", serde_json::json!({"defence_stab": 82, "defence_slash": 80, "defence_crush": 72})),
+			item(1115, "Iron platebody", "body", serde_json::json!({"defence_stab": 21, "defence_slash": 20, "defence_crush": 12})),
+			// magic armour has nothing against melee
+			item(577, "Blue wizard robe", "body", serde_json::json!({"attack_magic": 3, "defence_magic": 3})),
+		]);
+		assert!(gear_cache.get_by_id(577).is_none());
+		let ids = |defend_against| -> Vec<RunescapeInt> {
+			gear_cache.get_by_slot_full(Slot::Body, MAXED, AttackType::Slash, AttackStyle::Accurate, defend_against).iter()
+				.flat_map(|group| group.item_ids.clone())
+				.sorted()
+				.collect()
+		};
+		assert_eq!(ids(None), Vec::<RunescapeInt>::new());
+		assert_eq!(ids(Some(AttackType::Crush)), vec![1115, 1127]);
+	}
+
+	#[test]
+	fn spears_keep_their_controlled_stances() {
+		let gear_cache = cache(vec![weapon(1237, "Bronze spear", "weapon", serde_json::json!({ "attack_stab": 4 }), "spear")]);
+		let spears = |attack_type, style| -> Vec<ItemGroup> {
+			gear_cache.get_by_slot_full(Slot::Weapon, MAXED, attack_type, style, None).iter()
+				.filter(|group| group.item_ids.contains(&1237))
+				.cloned()
+				.collect()
+		};
+
+		let lunge = spears(AttackType::Stab, AttackStyle::Controlled);
+		assert_eq!(lunge.len(), 1);
+		assert_eq!(lunge[0].attack_value, 4);
+		assert_eq!(lunge[0].style_bonus, StyleBonus { attack: 1, strength: 1, defence: 1 });
+		assert_eq!(spears(AttackType::Stab, AttackStyle::Defensive)[0].style_bonus, StyleBonus { defence: 3, ..StyleBonus::default() });
+		assert!(spears(AttackType::Stab, AttackStyle::Accurate).is_empty());
+	}
+}
@@ -0,0 +1,130 @@
+use crate::runescape::gear::item_group::ItemGroup;
+use crate::runescape::monster::{Attribute, Target};
+
+// gear that's only better against some targets. like set pieces these are
+// recognized by name, so imbued and ornament variants count too
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub enum Passive {
+	// the black mask and every slayer helmet
+	SlayerHelm,
+	Salve,
+	// salve amulet (e) and (ei)
+	SalveEnchanted,
+	Arclight,
+	Darklight,
+	Silverlight,
+	DragonHunterLance,
+	Keris,
+}
+
+impl Passive {
+	pub fn of(name: &str) -> Option<Self> {
+		let lowercase = name.to_lowercase();
+		if lowercase.starts_with("black mask") || lowercase.contains("slayer helmet") {
+			Some(Passive::SlayerHelm)
+		} else if lowercase.starts_with("salve amulet") {
+			if lowercase.contains("(e") { Some(Passive::SalveEnchanted) } else { Some(Passive::Salve) }
+		} else if lowercase.starts_with("arclight") {
+			Some(Passive::Arclight)
+		} else if lowercase.starts_with("darklight") {
+			Some(Passive::Darklight)
+		} else if lowercase.starts_with("silverlight") {
+			Some(Passive::Silverlight)
+		} else if lowercase.starts_with("dragon hunter lance") {
+			Some(Passive::DragonHunterLance)
+		} else if lowercase.starts_with("keris") {
+			Some(Passive::Keris)
+		} else {
+			None
+		}
+	}
+
+	pub fn name(self) -> &'static str {
+		match self {
+			Passive::SlayerHelm        => "slayer helm",
+			Passive::Salve             => "salve amulet",
+			Passive::SalveEnchanted    => "salve amulet (e)",
+			Passive::Arclight          => "arclight",
+			Passive::Darklight         => "darklight",
+			Passive::Silverlight       => "silverlight",
+			Passive::DragonHunterLance => "dragon hunter lance",
+			Passive::Keris             => "keris",
+		}
+	}
+
+	pub fn applies(self, target: &Target) -> bool {
+		match self {
+			Passive::SlayerHelm        => target.on_task,
+			Passive::Salve             => target.is(Attribute::Undead),
+			Passive::SalveEnchanted    => target.is(Attribute::Undead),
+			Passive::Arclight          => target.is(Attribute::Demon),
+			Passive::Darklight         => target.is(Attribute::Demon),
+			Passive::Silverlight       => target.is(Attribute::Demon),
+			Passive::DragonHunterLance => target.is(Attribute::Dragon),
+			Passive::Keris             => target.is(Attribute::Kalphite),
+		}
+	}
+
+	// (accuracy, damage) multipliers against a target it applies to
+	pub fn multipliers(self) -> (f64, f64) {
+		match self {
+			Passive::SlayerHelm        => (7.0 / 6.0, 7.0 / 6.0),
+			Passive::Salve             => (7.0 / 6.0, 7.0 / 6.0),
+			Passive::SalveEnchanted    => (1.2, 1.2),
+			Passive::Arclight          => (1.7, 1.7),
+			Passive::Darklight         => (1.0, 1.6),
+			Passive::Silverlight       => (1.0, 1.6),
+			Passive::DragonHunterLance => (1.2, 1.2),
+			// the occasional triple hit is left out
+			Passive::Keris             => (1.0, 4.0 / 3.0),
+		}
+	}
+}
+
+// the names of every passive worn that works against `target`
+pub fn active_passives(items: &[ItemGroup], target: &Target) -> Vec<&'static str> {
+	items.iter()
+		.filter_map(|group| group.passive)
+		.filter(|passive| passive.applies(target))
+		.map(Passive::name)
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::runescape::osrsbox_db::types::AttackType;
+
+	#[test]
+	fn passives_are_recognized_by_name() {
+		assert_eq!(Passive::of("Salve amulet"), Some(Passive::Salve));
+		assert_eq!(Passive::of("Salve amulet(i)"), Some(Passive::Salve));
+		assert_eq!(Passive::of("Salve amulet (e)"), Some(Passive::SalveEnchanted));
+		assert_eq!(Passive::of("Salve amulet(ei)"), Some(Passive::SalveEnchanted));
+		assert_eq!(Passive::of("Black mask (10)"), Some(Passive::SlayerHelm));
+		assert_eq!(Passive::of("Slayer helmet (i)"), Some(Passive::SlayerHelm));
+		assert_eq!(Passive::of("Twisted slayer helmet"), Some(Passive::SlayerHelm));
+		assert_eq!(Passive::of("Dragon hunter lance"), Some(Passive::DragonHunterLance));
+		assert_eq!(Passive::of("Keris partisan"), Some(Passive::Keris));
+		assert_eq!(Passive::of("Dragon scimitar"), None);
+		assert_eq!(Passive::of("Amulet of strength"), None);
+	}
+
+	#[test]
+	fn passives_only_apply_to_matching_targets() {
+		let crab = Target::sand_crab();
+		let demon = Target { attributes: std::iter::once(Attribute::Demon).collect(), ..Target::sand_crab() };
+		assert!(Passive::Arclight.applies(&demon));
+		assert!(!Passive::Arclight.applies(&crab));
+		assert!(!Passive::Salve.applies(&demon));
+		assert!(!Passive::SlayerHelm.applies(&demon));
+		assert!(Passive::SlayerHelm.applies(&Target { on_task: true, ..Target::sand_crab() }));
+
+		let items = vec![
+			ItemGroup { passive: Some(Passive::Arclight), ..ItemGroup::empty_group(AttackType::Slash) },
+			ItemGroup { passive: Some(Passive::Salve), ..ItemGroup::empty_group(AttackType::Slash) },
+		];
+		assert_eq!(active_passives(&items, &demon), vec!["arclight"]);
+		assert!(active_passives(&items, &crab).is_empty());
+	}
+}
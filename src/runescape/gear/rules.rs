@@ -0,0 +1,297 @@
+use crate::runescape::RunescapeInt;
+use crate::runescape::gear::GearCache;
+use crate::runescape::gear::breakpoints::Breakpoint;
+use crate::runescape::gear::item_group::{filter_elided_items, ItemGroup, Objective};
+use crate::runescape::osrsbox_db::types::*;
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum RuleKind {
+	// only these items may be worn in their slot
+	Include,
+	// never wear these items, or anything in the slot
+	Exclude,
+	// always wear this item, the slot isn't searched
+	Pin,
+}
+
+#[derive(Debug, Clone)]
+pub enum Selector {
+	// an item id or a name, looked up like any other item query
+	Item(String),
+	Slot(Slot),
+}
+
+// a rule as given on the command line, before names are looked up
+#[derive(Debug, Clone)]
+pub struct Rule {
+	pub kind: RuleKind,
+	pub selector: Selector,
+}
+
+impl Rule {
+	// "slot:head", an item id, or an item name
+	pub fn parse(kind: RuleKind, value: &str) -> Result<Self, String> {
+		let selector = match value.strip_prefix("slot:") {
+			Some(slot) => Selector::Slot(slot.parse()?),
+			None       => Selector::Item(value.to_string()),
+		};
+		Ok(Self { kind, selector })
+	}
+}
+
+#[derive(Debug, Clone)]
+struct ResolvedRule {
+	kind: RuleKind,
+	slot: Slot,
+	// None covers the whole slot
+	item: Option<(RunescapeInt, String)>,
+}
+
+impl std::fmt::Display for ResolvedRule {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let kind = match self.kind {
+			RuleKind::Include => "include",
+			RuleKind::Exclude => "exclude",
+			RuleKind::Pin     => "pin",
+		};
+		match &self.item {
+			Some((id, name)) => write!(f, "{} {} ({}) in {:?}", kind, name, id, self.slot),
+			None             => write!(f, "{} everything in {:?}", kind, self.slot),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GearRules {
+	rules: Vec<ResolvedRule>,
+}
+
+impl GearRules {
+	pub fn resolve(rules: &[Rule], gear_cache: &GearCache) -> Result<Self, String> {
+		let mut resolved: Vec<ResolvedRule> = Vec::with_capacity(rules.len());
+		for rule in rules {
+			let rule = match &rule.selector {
+				Selector::Item(query) => {
+					let item = gear_cache.find(query)?;
+					// excluding something locked is harmless, wearing it isn't
+					if let (true, Some(reason)) = (rule.kind != RuleKind::Exclude, gear_cache.lock_reason(item.id)) {
+						return Err(reason);
+					}
+					ResolvedRule { kind: rule.kind, slot: item.equipment.slot.worn_in(), item: Some((item.id, item.name.clone())) }
+				},
+				Selector::Slot(slot) => ResolvedRule { kind: rule.kind, slot: slot.worn_in(), item: None },
+			};
+			let slot = rule.slot;
+			match (rule.kind, &rule.item) {
+				(RuleKind::Pin, None) => return Err(format!("{}: only single items can be pinned", rule)),
+				(RuleKind::Exclude, None) if slot == Slot::Weapon => return Err(format!("{}: the weapon slot can't be left empty", rule)),
+				_ => {},
+			}
+			if let Some(other) = resolved.iter().find(|other| other.slot == slot && (other.kind == RuleKind::Pin || rule.kind == RuleKind::Pin)) {
+				return Err(format!("{} conflicts with {}", rule, other));
+			}
+			resolved.push(rule);
+		}
+		Ok(Self { rules: resolved })
+	}
+
+	pub fn descriptions(&self) -> Vec<String> {
+		self.rules.iter().map(|rule| rule.to_string()).collect()
+	}
+
+	fn matches(rule: &ResolvedRule, id: RunescapeInt) -> bool {
+		match &rule.item {
+			Some((rule_id, _)) => *rule_id == id,
+			None               => true,
+		}
+	}
+
+	// the groups the rules leave in one slot. a pinned slot is only the pinned
+	// item, until then the slot is searched as usual so there's always a weapon
+	// to fight with, and `skipped_pins` says why
+	pub fn allowed(&self, slot: Slot, groups: &[ItemGroup]) -> Vec<ItemGroup> {
+		let slot_rules: Vec<&ResolvedRule> = self.rules.iter().filter(|rule| rule.slot == slot).collect();
+
+		if let Some(pin) = slot_rules.iter().find(|rule| rule.kind == RuleKind::Pin) {
+			let (id, _) = pin.item.as_ref().unwrap();
+			if let Some(group) = groups.iter().find(|group| group.item_ids.contains(id)) {
+				return vec![ItemGroup { item_ids: vec![*id], ..group.clone() }];
+			}
+		}
+
+		let includes: Vec<&ResolvedRule> = slot_rules.iter().filter(|rule| rule.kind == RuleKind::Include).copied().collect();
+		let excludes: Vec<&ResolvedRule> = slot_rules.iter().filter(|rule| rule.kind == RuleKind::Exclude).copied().collect();
+		let allowed = |id: &RunescapeInt| {
+			(includes.is_empty() || includes.iter().any(|rule| Self::matches(rule, *id))) &&
+			!excludes.iter().any(|rule| Self::matches(rule, *id))
+		};
+
+		groups.iter()
+			.filter_map(|group| {
+				let item_ids: Vec<RunescapeInt> = group.item_ids.iter().filter(|id| allowed(id)).copied().collect();
+				// the empty group stands for wearing nothing, which is always allowed
+				if item_ids.is_empty() && !group.item_ids.is_empty() {
+					None
+				} else {
+					Some(ItemGroup { item_ids, ..group.clone() })
+				}
+			})
+			.collect()
+	}
+
+	// the allowed groups of one slot, elided
+	pub fn candidates(&self, allowed: &[ItemGroup], attack_type: AttackType, objectives: &[Objective]) -> Vec<ItemGroup> {
+		if allowed.is_empty() {
+			vec![ItemGroup::empty_group(attack_type)]
+		} else {
+			filter_elided_items(allowed, objectives)
+		}
+	}
+
+	// pins that aren't in one step's gear, with why the search had to skip them.
+	// `levels` and `style` are the ones the step was searched with
+	pub fn skipped_pins(&self, worn: &[ItemGroup], gear_cache: &GearCache, levels: Breakpoint, style: AttackStyle) -> Vec<String> {
+		let stats: Stats = levels.into();
+		self.rules.iter()
+			.filter(|rule| rule.kind == RuleKind::Pin)
+			.filter_map(|rule| {
+				let (id, _) = rule.item.as_ref()?;
+				if worn.iter().any(|group| group.item_ids.contains(id)) {
+					return None;
+				}
+				let item = gear_cache.get_by_id(*id)?;
+				let missing = item.equipment.requirements.as_ref().map_or_else(Vec::new, |required| missing_levels(required, &stats));
+				let reason = match &item.weapon {
+					_ if !missing.is_empty() => format!("needs {}", missing.join(", ")),
+					Some(weapon) if !weapon.stance_table().iter().any(|stance| stance.attack_style == style) => {
+						format!("has no {} stance", format!("{:?}", style).to_lowercase())
+					},
+					_ => "can't be worn with the rest of this step's gear".to_string(),
+				};
+				Some(format!("{} skipped: {}", rule, reason))
+			})
+			.collect()
+	}
+
+	// the rules that put a group in the plan, if any did
+	pub fn reasons(&self, group: &ItemGroup) -> Vec<String> {
+		self.rules.iter()
+			.filter(|rule| rule.kind != RuleKind::Exclude)
+			.filter(|rule| match &rule.item {
+				Some((id, _)) => group.item_ids.contains(id),
+				None          => false,
+			})
+			.map(|rule| rule.to_string())
+			.collect()
+	}
+}
+
+// the levels in `required` that `stats` falls short of, like "60 attack"
+fn missing_levels(required: &Stats, stats: &Stats) -> Vec<String> {
+	[
+		(required.attack,    stats.attack,    "attack"),
+		(required.strength,  stats.strength,  "strength"),
+		(required.defence,   stats.defence,   "defence"),
+		(required.hitpoints, stats.hitpoints, "hitpoints"),
+		(required.prayer,    stats.prayer,    "prayer"),
+		(required.ranged,    stats.ranged,    "ranged"),
+		(required.magic,     stats.magic,     "magic"),
+	].iter()
+		.filter(|(required, have, _)| required > have)
+		.map(|(required, _, skill)| format!("{} {}", required.unwrap(), skill))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::runescape::gear::tests::{cache, weapon, MAXED};
+
+	// two scimitars with the same stats share a group
+	fn gear_cache() -> GearCache {
+		cache(vec![
+			weapon(1333, "Rune scimitar", "weapon", serde_json::json!({"attack_slash": 45, "melee_strength": 44}), "slash_sword"),
+			weapon(6611, "White scimitar", "weapon", serde_json::json!({"attack_slash": 45, "melee_strength": 44}), "slash_sword"),
+			weapon(4587, "Dragon scimitar", "weapon", serde_json::json!({"attack_slash": 67, "melee_strength": 66, "requirements": {"attack": 60}}), "slash_sword"),
+			weapon(1305, "Dragon longsword", "weapon", serde_json::json!({"attack_slash": 69, "melee_strength": 71, "requirements": {"attack": 60}}), "slash_sword"),
+		])
+	}
+
+	fn rules(gear_cache: &GearCache, rules: &[(RuleKind, &str)]) -> Result<GearRules, String> {
+		let rules: Vec<Rule> = rules.iter().map(|(kind, value)| Rule::parse(*kind, value).unwrap()).collect();
+		GearRules::resolve(&rules, gear_cache)
+	}
+
+	fn weapons(gear_cache: &GearCache, levels: Breakpoint) -> Vec<ItemGroup> {
+		gear_cache.get_by_slot_full(Slot::Weapon, levels, AttackType::Slash, AttackStyle::Accurate, None).to_vec()
+	}
+
+	fn ids(groups: &[ItemGroup]) -> Vec<RunescapeInt> {
+		let mut ids: Vec<RunescapeInt> = groups.iter().flat_map(|group| group.item_ids.clone()).collect();
+		ids.sort();
+		ids
+	}
+
+	#[test]
+	fn includes_restrict_their_slot() {
+		let gear_cache = gear_cache();
+		let rules = rules(&gear_cache, &[(RuleKind::Include, "4587"), (RuleKind::Include, "1305")]).unwrap();
+		assert_eq!(ids(&rules.allowed(Slot::Weapon, &weapons(&gear_cache, MAXED))), vec![1305, 4587]);
+		// other slots are left alone
+		let head = vec![ItemGroup { item_ids: vec![1163], ..ItemGroup::empty_group(AttackType::Slash) }];
+		assert_eq!(ids(&rules.allowed(Slot::Head, &head)), vec![1163]);
+	}
+
+	#[test]
+	fn excludes_take_one_member_out_of_a_group() {
+		let gear_cache = gear_cache();
+		let rules = rules(&gear_cache, &[(RuleKind::Exclude, "6611")]).unwrap();
+		let allowed = rules.allowed(Slot::Weapon, &weapons(&gear_cache, MAXED));
+		let scimitars = allowed.iter().find(|group| group.item_ids.contains(&1333)).unwrap();
+		assert_eq!(scimitars.item_ids, vec![1333]);
+		assert_eq!(ids(&allowed), vec![1305, 1333, 4587]);
+	}
+
+	#[test]
+	fn pins_skip_the_search_while_they_can_be_worn() {
+		let gear_cache = gear_cache();
+		let rules = rules(&gear_cache, &[(RuleKind::Pin, "4587")]).unwrap();
+		let allowed = rules.allowed(Slot::Weapon, &weapons(&gear_cache, MAXED));
+		assert_eq!(ids(&allowed), vec![4587]);
+		assert!(rules.skipped_pins(&allowed, &gear_cache, MAXED, AttackStyle::Accurate).is_empty());
+
+		// below 60 attack the slot is searched as usual, and the plan says why
+		let low = Breakpoint { attack: 40, ..MAXED };
+		let allowed = rules.allowed(Slot::Weapon, &weapons(&gear_cache, low));
+		assert_eq!(ids(&allowed), vec![1333, 6611]);
+		assert_eq!(rules.skipped_pins(&allowed, &gear_cache, low, AttackStyle::Accurate), vec![
+			"pin Dragon scimitar (4587) in Weapon skipped: needs 60 attack".to_string(),
+		]);
+	}
+
+	#[test]
+	fn conflicting_rules_are_rejected() {
+		let gear_cache = gear_cache();
+		let error = rules(&gear_cache, &[(RuleKind::Pin, "4587"), (RuleKind::Pin, "1305")]).unwrap_err();
+		assert_eq!(error, "pin Dragon longsword (1305) in Weapon conflicts with pin Dragon scimitar (4587) in Weapon");
+		assert!(rules(&gear_cache, &[(RuleKind::Include, "1333"), (RuleKind::Pin, "4587")]).is_err());
+		assert!(rules(&gear_cache, &[(RuleKind::Pin, "slot:head")]).unwrap_err().contains("only single items can be pinned"));
+	}
+
+	#[test]
+	fn the_weapon_slot_cant_be_excluded() {
+		let gear_cache = gear_cache();
+		let error = rules(&gear_cache, &[(RuleKind::Exclude, "slot:weapon")]).unwrap_err();
+		assert_eq!(error, "exclude everything in Weapon: the weapon slot can't be left empty");
+		assert!(rules(&gear_cache, &[(RuleKind::Exclude, "slot:head")]).is_ok());
+	}
+
+	#[test]
+	fn missing_levels_lists_what_falls_short() {
+		let required = Stats { attack: Some(60), strength: None, defence: Some(40), hitpoints: None, prayer: None, ranged: None, magic: None };
+		let stats: Stats = Breakpoint { attack: 50, strength: 1, defence: 40, hitpoints: 10, prayer: 1, ranged: 1, magic: 1 }.into();
+		assert_eq!(missing_levels(&required, &stats), vec!["60 attack".to_string()]);
+		let stats: Stats = Breakpoint { attack: 60, strength: 1, defence: 1, hitpoints: 10, prayer: 1, ranged: 1, magic: 1 }.into();
+		assert_eq!(missing_levels(&required, &stats), vec!["40 defence".to_string()]);
+	}
+}
@@ -0,0 +1,159 @@
+use crate::runescape::RunescapeInt;
+use crate::runescape::osrsbox_db::types::Item;
+
+// matches below this aren't worth showing
+const MIN_SCORE: f64 = 0.5;
+
+#[derive(Debug, Clone)]
+struct Entry {
+	id: RunescapeInt,
+	tokens: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchIndex {
+	entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Candidate {
+	pub id: RunescapeInt,
+	pub score: f64,
+}
+
+// lowercase words, punctuation is dropped except for the pluses in poison
+// variants, so "Dragon dagger(p++)" becomes ["dragon", "dagger", "p++"]
+fn tokenize(name: &str) -> Vec<String> {
+	name.to_lowercase()
+		.chars()
+		.map(|c| if c.is_alphanumeric() || c == '+' { c } else { ' ' })
+		.collect::<String>()
+		.split_whitespace()
+		.map(String::from)
+		.collect()
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+	let a: Vec<char> = a.chars().collect();
+	let b: Vec<char> = b.chars().collect();
+	let mut previous: Vec<usize> = (0..=b.len()).collect();
+	for (i, ca) in a.iter().enumerate() {
+		let mut current = vec![i + 1; b.len() + 1];
+		for (j, cb) in b.iter().enumerate() {
+			let substitution = previous[j] + if ca == cb { 0 } else { 1 };
+			current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+		}
+		previous = current;
+	}
+	previous[b.len()]
+}
+
+// how well one query word matches one name word, from 0 to 1
+fn token_similarity(query: &str, name: &str) -> f64 {
+	if query == name {
+		return 1.0;
+	}
+	// "scim" for "scimitar", and less surely "d" for "dragon"
+	if name.starts_with(query) {
+		return if query.len() >= 3 { 0.9 } else { 0.7 };
+	}
+	let longest = query.chars().count().max(name.chars().count());
+	1.0 - edit_distance(query, name) as f64 / longest as f64
+}
+
+impl SearchIndex {
+	pub fn new<'a, T: IntoIterator<Item=&'a Item>>(items: T) -> Self {
+		Self {
+			entries: items.into_iter()
+				.map(|item| Entry { id: item.id, tokens: tokenize(&item.name) })
+				.collect(),
+		}
+	}
+
+	// every query word is matched against its closest name word. names with
+	// words the query didn't ask for, like variant suffixes, rank a bit lower
+	fn score(query: &[String], entry: &Entry) -> f64 {
+		if query.is_empty() || entry.tokens.is_empty() {
+			return 0.0;
+		}
+		let mut matched = vec![false; entry.tokens.len()];
+		let mut total = 0.0;
+		for word in query {
+			let best = entry.tokens.iter()
+				.enumerate()
+				.map(|(i, token)| (i, token_similarity(word, token)))
+				.fold((0, 0.0), |best, next| if next.1 > best.1 { next } else { best });
+			if best.1 >= MIN_SCORE {
+				matched[best.0] = true;
+			}
+			total += best.1;
+		}
+		let coverage = matched.iter().filter(|m| **m).count() as f64 / entry.tokens.len() as f64;
+		total / query.len() as f64 * (0.8 + 0.2 * coverage)
+	}
+
+	// best matches first
+	pub fn search(&self, query: &str, limit: usize) -> Vec<Candidate> {
+		let query = tokenize(query);
+		let mut candidates: Vec<Candidate> = self.entries.iter()
+			.map(|entry| Candidate { id: entry.id, score: Self::score(&query, entry) })
+			.filter(|candidate| candidate.score >= MIN_SCORE)
+			.collect();
+		candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap().then(a.id.cmp(&b.id)));
+		candidates.truncate(limit);
+		candidates
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn index(names: &[&str]) -> SearchIndex {
+		SearchIndex {
+			entries: names.iter().enumerate()
+				.map(|(i, name)| Entry { id: i as RunescapeInt, tokens: tokenize(name) })
+				.collect(),
+		}
+	}
+
+	fn names<'a>(names: &[&'a str], query: &str) -> Vec<&'a str> {
+		index(names).search(query, 10).iter().map(|candidate| names[candidate.id as usize]).collect()
+	}
+
+	const NAMES: [&str; 5] = ["Dragon scimitar", "Dragon dagger", "Dragon dagger(p++)", "Rune scimitar", "Bronze sword"];
+
+	#[test]
+	fn names_are_split_into_lowercase_words() {
+		assert_eq!(tokenize("Dragon dagger(p++)"), vec!["dragon", "dagger", "p++"]);
+		assert_eq!(tokenize("Dharok's greataxe 100"), vec!["dharok", "s", "greataxe", "100"]);
+		assert!(tokenize("  ").is_empty());
+	}
+
+	#[test]
+	fn edit_distance_counts_single_changes() {
+		assert_eq!(edit_distance("kitten", "sitting"), 3);
+		assert_eq!(edit_distance("", "abc"), 3);
+		assert_eq!(edit_distance("scimitar", "scimitar"), 0);
+		assert_eq!(edit_distance("scimtar", "scimitar"), 1);
+	}
+
+	#[test]
+	fn prefixes_of_every_word_match() {
+		assert_eq!(names(&NAMES, "d scim")[0], "Dragon scimitar");
+		assert_eq!(names(&NAMES, "scim"), vec!["Dragon scimitar", "Rune scimitar"]);
+	}
+
+	#[test]
+	fn typos_within_the_threshold_match() {
+		assert_eq!(names(&NAMES, "dragon scimtar")[0], "Dragon scimitar");
+		assert_eq!(names(&NAMES, "bronse swrod"), vec!["Bronze sword"]);
+		assert!(names(&NAMES, "whip").is_empty());
+	}
+
+	#[test]
+	fn exact_names_rank_first() {
+		assert_eq!(names(&NAMES, "dragon dagger")[..2], ["Dragon dagger", "Dragon dagger(p++)"]);
+		assert_eq!(names(&NAMES, "dragon dagger p++")[0], "Dragon dagger(p++)");
+	}
+}
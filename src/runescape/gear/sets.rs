@@ -0,0 +1,121 @@
+use crate::runescape::gear::item_group::ItemGroup;
+use crate::runescape::osrsbox_db::types::*;
+
+// one piece of gear that takes part in a set effect. pieces are recognized by
+// name so every variant of an item, like barrows degradation, counts
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub enum SetPiece {
+	VoidHelm,
+	VoidTop,
+	VoidRobe,
+	VoidGloves,
+	ObsidianHelm,
+	ObsidianBody,
+	ObsidianLegs,
+	ObsidianWeapon,
+	BerserkerNecklace,
+	DharoksHelm,
+	DharoksBody,
+	DharoksLegs,
+	DharoksWeapon,
+	InquisitorsHelm,
+	InquisitorsBody,
+	InquisitorsLegs,
+	JusticiarHelm,
+	JusticiarBody,
+	JusticiarLegs,
+}
+
+const PIECE_NAMES: [(&str, SetPiece); 24] = [
+	("Void melee helm",         SetPiece::VoidHelm),
+	("Void knight top",         SetPiece::VoidTop),
+	("Elite void top",          SetPiece::VoidTop),
+	("Void knight robe",        SetPiece::VoidRobe),
+	("Elite void robe",         SetPiece::VoidRobe),
+	("Void knight gloves",      SetPiece::VoidGloves),
+	("Obsidian helmet",         SetPiece::ObsidianHelm),
+	("Obsidian platebody",      SetPiece::ObsidianBody),
+	("Obsidian platelegs",      SetPiece::ObsidianLegs),
+	("Toktz-xil-ak",            SetPiece::ObsidianWeapon),
+	("Toktz-xil-ek",            SetPiece::ObsidianWeapon),
+	("Tzhaar-ket-em",           SetPiece::ObsidianWeapon),
+	("Tzhaar-ket-om",           SetPiece::ObsidianWeapon),
+	("Berserker necklace",      SetPiece::BerserkerNecklace),
+	("Dharok's helm",           SetPiece::DharoksHelm),
+	("Dharok's platebody",      SetPiece::DharoksBody),
+	("Dharok's platelegs",      SetPiece::DharoksLegs),
+	("Dharok's greataxe",       SetPiece::DharoksWeapon),
+	("Inquisitor's great helm", SetPiece::InquisitorsHelm),
+	("Inquisitor's hauberk",    SetPiece::InquisitorsBody),
+	("Inquisitor's plateskirt", SetPiece::InquisitorsLegs),
+	("Justiciar faceguard",     SetPiece::JusticiarHelm),
+	("Justiciar chestguard",    SetPiece::JusticiarBody),
+	("Justiciar legguards",     SetPiece::JusticiarLegs),
+];
+
+impl SetPiece {
+	pub fn of(name: &str) -> Option<Self> {
+		PIECE_NAMES.iter()
+			.find(|(prefix, _)| name.starts_with(prefix))
+			.map(|(_, piece)| *piece)
+	}
+
+	pub fn slot(self) -> Slot {
+		use SetPiece::*;
+
+		match self {
+			VoidHelm | ObsidianHelm | DharoksHelm | InquisitorsHelm | JusticiarHelm => Slot::Head,
+			VoidTop | ObsidianBody | DharoksBody | InquisitorsBody | JusticiarBody   => Slot::Body,
+			VoidRobe | ObsidianLegs | DharoksLegs | InquisitorsLegs | JusticiarLegs  => Slot::Legs,
+			VoidGloves                                                               => Slot::Hands,
+			BerserkerNecklace                                                        => Slot::Neck,
+			ObsidianWeapon | DharoksWeapon                                           => Slot::Weapon,
+		}
+	}
+}
+
+// the pieces that have to be worn together for an effect, every piece is
+// worn in its own slot
+pub const VOID: &[SetPiece] = &[SetPiece::VoidHelm, SetPiece::VoidTop, SetPiece::VoidRobe, SetPiece::VoidGloves];
+pub const OBSIDIAN: &[SetPiece] = &[SetPiece::ObsidianHelm, SetPiece::ObsidianBody, SetPiece::ObsidianLegs, SetPiece::ObsidianWeapon];
+pub const BERSERKER: &[SetPiece] = &[SetPiece::BerserkerNecklace, SetPiece::ObsidianWeapon];
+pub const OBSIDIAN_BERSERKER: &[SetPiece] = &[SetPiece::ObsidianHelm, SetPiece::ObsidianBody, SetPiece::ObsidianLegs, SetPiece::ObsidianWeapon, SetPiece::BerserkerNecklace];
+pub const DHAROKS: &[SetPiece] = &[SetPiece::DharoksHelm, SetPiece::DharoksBody, SetPiece::DharoksLegs, SetPiece::DharoksWeapon];
+pub const INQUISITORS: &[SetPiece] = &[SetPiece::InquisitorsHelm, SetPiece::InquisitorsBody, SetPiece::InquisitorsLegs];
+pub const JUSTICIAR: &[SetPiece] = &[SetPiece::JusticiarHelm, SetPiece::JusticiarBody, SetPiece::JusticiarLegs];
+
+pub const FULL_SETS: [(&str, &[SetPiece]); 7] = [
+	("void",               VOID),
+	("obsidian",           OBSIDIAN),
+	("berserker",          BERSERKER),
+	("obsidian berserker", OBSIDIAN_BERSERKER),
+	("dharok's",           DHAROKS),
+	("inquisitor's",       INQUISITORS),
+	("justiciar",          JUSTICIAR),
+];
+
+// the names of every full set worn
+pub fn worn_sets(items: &[ItemGroup]) -> Vec<&'static str> {
+	FULL_SETS.iter()
+		.filter(|(_, pieces)| pieces.iter().all(|piece| items.iter().any(|group| group.set_piece == Some(*piece))))
+		.map(|(name, _)| *name)
+		.collect()
+}
+
+// the gear to search for one full set: the set's slots only hold its pieces,
+// every other slot keeps its usual candidates. None if a piece can't be worn
+pub fn set_search(set: &[SetPiece], slots: &[Slot], allowed: &[Vec<ItemGroup>], candidates: &[Vec<ItemGroup>]) -> Option<Vec<Vec<ItemGroup>>> {
+	slots.iter().enumerate()
+		.map(|(i, slot)| {
+			let pieces: Vec<SetPiece> = set.iter().copied().filter(|piece| piece.slot() == *slot).collect();
+			if pieces.is_empty() {
+				return Some(candidates[i].clone());
+			}
+			let groups: Vec<ItemGroup> = allowed[i].iter()
+				.filter(|group| group.set_piece.is_some_and(|piece| pieces.contains(&piece)))
+				.cloned()
+				.collect();
+			if groups.is_empty() { None } else { Some(groups) }
+		})
+		.collect()
+}
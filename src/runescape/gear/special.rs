@@ -0,0 +1,288 @@
+use std::collections::BTreeMap;
+
+use crate::runescape::RunescapeInt;
+use crate::runescape::graph::damage::HitDistribution;
+use crate::runescape::osrsbox_db::types::*;
+
+// special energy comes back at 10% every 30 seconds
+const ENERGY_PER_SECOND: f64 = 10.0 / 30.0;
+// the special bar holds at most this much energy
+pub const FULL_BAR: f64 = 100.0;
+
+// weapons whose special attack is worth spending energy on while training.
+// like set pieces these are recognized by name, so poisoned and ornament
+// variants count too
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub enum SpecialAttack {
+	DragonDagger,
+	DragonClaws,
+	GraniteMaul,
+	DragonLongsword,
+	DragonMace,
+	DragonScimitar,
+	DragonWarhammer,
+	BandosGodsword,
+	ElderMaul,
+	Arclight,
+}
+
+const SPECIAL_NAMES: [(&str, SpecialAttack); 10] = [
+	("Dragon dagger",    SpecialAttack::DragonDagger),
+	("Dragon claws",     SpecialAttack::DragonClaws),
+	("Granite maul",     SpecialAttack::GraniteMaul),
+	("Dragon longsword", SpecialAttack::DragonLongsword),
+	("Dragon mace",      SpecialAttack::DragonMace),
+	("Dragon scimitar",  SpecialAttack::DragonScimitar),
+	("Dragon warhammer", SpecialAttack::DragonWarhammer),
+	("Bandos godsword",  SpecialAttack::BandosGodsword),
+	("Elder maul",       SpecialAttack::ElderMaul),
+	("Arclight",         SpecialAttack::Arclight),
+];
+
+impl SpecialAttack {
+	pub fn of(name: &str) -> Option<Self> {
+		SPECIAL_NAMES.iter()
+			.find(|(prefix, _)| name.starts_with(prefix))
+			.map(|(_, special)| *special)
+	}
+
+	pub fn name(self) -> &'static str {
+		SPECIAL_NAMES.iter()
+			.find(|(_, special)| *special == self)
+			.map(|(name, _)| *name)
+			.unwrap()
+	}
+
+	// percent of the special bar one special attack uses
+	pub fn energy(self) -> f64 {
+		match self {
+			SpecialAttack::DragonDagger    => 25.0,
+			SpecialAttack::DragonClaws     => 50.0,
+			SpecialAttack::GraniteMaul     => 60.0,
+			SpecialAttack::DragonLongsword => 25.0,
+			SpecialAttack::DragonMace      => 25.0,
+			SpecialAttack::DragonScimitar  => 55.0,
+			SpecialAttack::DragonWarhammer => 50.0,
+			SpecialAttack::BandosGodsword  => 50.0,
+			SpecialAttack::ElderMaul       => 50.0,
+			SpecialAttack::Arclight        => 50.0,
+		}
+	}
+
+	// (accuracy, damage) multipliers on the max attack roll and max hit
+	pub fn multipliers(self) -> (f64, f64) {
+		match self {
+			SpecialAttack::DragonDagger    => (1.15, 1.15),
+			SpecialAttack::DragonClaws     => (1.0, 1.0),
+			SpecialAttack::GraniteMaul     => (1.0, 1.0),
+			SpecialAttack::DragonLongsword => (1.0, 1.25),
+			SpecialAttack::DragonMace      => (1.25, 1.5),
+			SpecialAttack::DragonScimitar  => (1.25, 1.0),
+			SpecialAttack::DragonWarhammer => (1.0, 1.5),
+			SpecialAttack::BandosGodsword  => (2.0, 1.21),
+			SpecialAttack::ElderMaul       => (1.25, 1.0),
+			SpecialAttack::Arclight        => (1.0, 1.0),
+		}
+	}
+
+	// the granite maul's special hits straight away, without waiting for the
+	// next attack
+	pub fn instant(self) -> bool {
+		self == SpecialAttack::GraniteMaul
+	}
+
+	// the damage of one special attack, given the chance one of its accuracy
+	// rolls lands and its max hit
+	pub fn hits(self, hit_chance: f64, max_hit: RunescapeInt) -> HitDistribution {
+		match self {
+			SpecialAttack::DragonDagger => HitDistribution::uniform(hit_chance, max_hit).repeat(2),
+			SpecialAttack::DragonClaws  => claws(hit_chance, max_hit),
+			_                           => HitDistribution::uniform(hit_chance, max_hit),
+		}
+	}
+}
+
+// the claws roll accuracy up to four times and the first roll that lands
+// decides how the four hits add up. the couple of damage done when all four
+// miss is left out
+fn claws(hit_chance: f64, max_hit: RunescapeInt) -> HitDistribution {
+	let miss = 1.0 - hit_chance;
+	// the first, second, third and fourth roll landing, each with the range of the total
+	HitDistribution::blend(&[
+		(hit_chance,                HitDistribution::uniform_with_min(1.0, max_hit, max_hit * 2 - 1)),
+		(miss * hit_chance,         HitDistribution::uniform_with_min(1.0, max_hit * 3 / 4, max_hit * 7 / 4)),
+		(miss.powi(2) * hit_chance, HitDistribution::uniform_with_min(1.0, max_hit / 2, max_hit * 3 / 2)),
+		(miss.powi(3) * hit_chance, HitDistribution::uniform_with_min(1.0, max_hit / 4, max_hit * 5 / 4)),
+		(miss.powi(4),              HitDistribution::zero()),
+	])
+}
+
+// a weapon kept in the inventory and swapped to for its special attack
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct SpecialWeapon {
+	pub special: SpecialAttack,
+	// it attacks with whichever of stab, slash and crush it's best at
+	pub attack_type: AttackType,
+	pub attack_value: RunescapeInt,
+	pub strength_value: RunescapeInt,
+	pub attack_speed: RunescapeInt,
+}
+
+impl SpecialWeapon {
+	pub fn from_item(item: &Item) -> Result<Self, String> {
+		let special = SpecialAttack::of(&item.name).ok_or_else(|| format!("{} has no special attack to train with", item.name))?;
+		let weapon = item.weapon.as_ref().ok_or_else(|| format!("{} isn't a weapon", item.name))?;
+		let equipment = &item.equipment;
+		let (attack_value, attack_type) = [
+			(equipment.attack_stab,  AttackType::Stab),
+			(equipment.attack_slash, AttackType::Slash),
+			(equipment.attack_crush, AttackType::Crush),
+		].iter().copied().max_by_key(|(value, _)| *value).unwrap();
+		Ok(Self {
+			special,
+			attack_type,
+			attack_value,
+			strength_value: equipment.melee_strength,
+			attack_speed: weapon.attack_speed,
+		})
+	}
+}
+
+// how special attacks are used while training
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub struct Specials {
+	// spend energy on the wielded weapon's special, or the swap's if there is one
+	pub enabled: bool,
+	pub swap: Option<SpecialWeapon>,
+	// the lightbearer doubles energy regeneration, it's worn in place of any other ring
+	pub lightbearer: bool,
+}
+
+impl Specials {
+	pub fn energy_per_second(&self) -> f64 {
+		if self.lightbearer { ENERGY_PER_SECOND * 2.0 } else { ENERGY_PER_SECOND }
+	}
+}
+
+// how the special bar gets spent against a target
+#[derive(Debug, Clone, Copy)]
+pub struct SpecialEnergy {
+	// the share of kills the defence reductions are thrown on
+	pub reduced_share: f64,
+	// what regenerates per second on top of that for the weapon's special
+	pub energy_per_second: f64,
+}
+
+// special attacks thrown at the start of a kill to lower the target's defence
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum DefenceReduction {
+	DragonWarhammer,
+	BandosGodsword,
+	Arclight,
+	ElderMaul,
+}
+
+impl DefenceReduction {
+	pub fn special(self) -> SpecialAttack {
+		match self {
+			DefenceReduction::DragonWarhammer => SpecialAttack::DragonWarhammer,
+			DefenceReduction::BandosGodsword  => SpecialAttack::BandosGodsword,
+			DefenceReduction::Arclight        => SpecialAttack::Arclight,
+			DefenceReduction::ElderMaul       => SpecialAttack::ElderMaul,
+		}
+	}
+
+	// in game ticks, the weapon is swapped to for the special alone
+	pub fn attack_speed(self) -> RunescapeInt {
+		match self {
+			DefenceReduction::Arclight => 4,
+			_                          => 6,
+		}
+	}
+
+	// the defence level left after a special that lands for `damage`
+	pub fn reduce(self, defence: RunescapeInt, base_defence: RunescapeInt, damage: RunescapeInt, demon: bool) -> RunescapeInt {
+		// a tenth of the base level against demons, a twentieth otherwise
+		let percent = if demon { 10 } else { 5 };
+		let reduced = match self {
+			DefenceReduction::DragonWarhammer => defence * 7 / 10,
+			DefenceReduction::ElderMaul       => defence * 65 / 100,
+			DefenceReduction::BandosGodsword  => defence - damage,
+			DefenceReduction::Arclight        => defence - base_defence * percent / 100 - 1,
+		};
+		reduced.max(0)
+	}
+}
+
+impl std::str::FromStr for DefenceReduction {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"dwh" | "dragon-warhammer" => Ok(DefenceReduction::DragonWarhammer),
+			"bgs" | "bandos-godsword"  => Ok(DefenceReduction::BandosGodsword),
+			"arclight"                 => Ok(DefenceReduction::Arclight),
+			"elder-maul"               => Ok(DefenceReduction::ElderMaul),
+			_                          => Err(format!("unknown defence reduction {}, expected dwh, bgs, arclight or elder-maul", s)),
+		}
+	}
+}
+
+// the target's defence level after its reductions with the chance of each,
+// and the damage, time and number of specials throwing them takes every kill
+#[derive(Debug, Clone)]
+pub struct ReducedDefence {
+	pub levels: BTreeMap<RunescapeInt, f64>,
+	pub damage: f64,
+	pub seconds: f64,
+	pub specials: usize,
+}
+
+impl ReducedDefence {
+	pub fn none(defence: RunescapeInt) -> Self {
+		Self {
+			levels: std::iter::once((defence, 1.0)).collect(),
+			damage: 0.0,
+			seconds: 0.0,
+			specials: 0,
+		}
+	}
+
+	pub fn average(&self) -> f64 {
+		self.levels.iter().map(|(level, chance)| *level as f64 * chance).sum()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::runescape::gear::{is_melee_gear, GearCache, WorldType, OSRSBOX_SLOTS};
+	use crate::runescape::gear::breakpoints::Breakpoint;
+
+	// every special weapon as osrsbox lists it, in the slot it lists it under
+	const SPECIAL_WEAPONS: &str = include_str!("../../../tests/fixtures/osrsbox/special-weapons.json");
+
+	#[test]
+	fn every_special_weapon_can_be_found_and_wielded() {
+		let items: Vec<Item> = serde_json::from_str(SPECIAL_WEAPONS).unwrap();
+		let gear_cache = GearCache::from_gear(items.iter().map(|item| (item.id, item.clone())).collect(), is_melee_gear, WorldType::Members);
+		let maxed = Breakpoint { attack: 99, strength: 99, defence: 99, hitpoints: 99, prayer: 99, ranged: 99, magic: 99 };
+
+		for (name, special) in &SPECIAL_NAMES {
+			let listed = items.iter().find(|item| SpecialAttack::of(&item.name) == Some(*special)).unwrap();
+			assert!(OSRSBOX_SLOTS.contains(&listed.equipment.slot), "{} is in a slot that's never fetched", name);
+
+			let item = gear_cache.find(&name.to_lowercase()).unwrap_or_else(|e| panic!("{}: {}", name, e));
+			assert_eq!(SpecialAttack::of(&item.name), Some(*special));
+			assert_eq!(SpecialWeapon::from_item(item).unwrap().special, *special);
+
+			let wieldable = [AttackType::Stab, AttackType::Slash, AttackType::Crush].iter().any(|attack_type| {
+				[AttackStyle::Accurate, AttackStyle::Aggressive].iter().any(|style| {
+					gear_cache.get_by_slot_full(Slot::Weapon, maxed, *attack_type, *style, None).iter()
+						.any(|group| group.special == Some(*special))
+				})
+			});
+			assert!(wieldable, "{} is never searched as a weapon", name);
+		}
+	}
+}
@@ -0,0 +1,109 @@
+use crate::runescape::RunescapeInt;
+use crate::runescape::gear::special::Specials;
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Potion {
+	None,
+	// attack/strength/combat potions
+	Regular,
+	// super attack/strength/combat potions
+	Super,
+}
+
+impl Potion {
+	pub fn boost(self, level: RunescapeInt) -> RunescapeInt {
+		match self {
+			Potion::None    => 0,
+			Potion::Regular => 3 + level * 10 / 100,
+			Potion::Super   => 5 + level * 15 / 100,
+		}
+	}
+}
+
+impl std::str::FromStr for Potion {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"none"    => Ok(Potion::None),
+			"regular" => Ok(Potion::Regular),
+			"super"   => Ok(Potion::Super),
+			_         => Err(format!("unknown potion {}, expected none, regular or super", s)),
+		}
+	}
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Prayer {
+	None,
+	// clarity of thought and burst of strength
+	Low,
+	// improved reflexes and superhuman strength
+	Medium,
+	// incredible reflexes and ultimate strength
+	High,
+	Chivalry,
+	Piety,
+}
+
+impl Prayer {
+	// (attack, strength) multipliers
+	pub fn multipliers(self) -> (f64, f64) {
+		match self {
+			Prayer::None     => (1.0, 1.0),
+			Prayer::Low      => (1.05, 1.05),
+			Prayer::Medium   => (1.10, 1.10),
+			Prayer::High     => (1.15, 1.15),
+			Prayer::Chivalry => (1.15, 1.18),
+			Prayer::Piety    => (1.20, 1.23),
+		}
+	}
+
+	// only chivalry and piety come with a defence boost, the other tiers
+	// are attack and strength prayers alone
+	pub fn defence_multiplier(self) -> f64 {
+		match self {
+			Prayer::Chivalry => 1.20,
+			Prayer::Piety    => 1.25,
+			_                => 1.0,
+		}
+	}
+}
+
+impl std::str::FromStr for Prayer {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"none"     => Ok(Prayer::None),
+			"low"      => Ok(Prayer::Low),
+			"medium"   => Ok(Prayer::Medium),
+			"high"     => Ok(Prayer::High),
+			"chivalry" => Ok(Prayer::Chivalry),
+			"piety"    => Ok(Prayer::Piety),
+			_          => Err(format!("unknown prayer {}, expected none, low, medium, high, chivalry or piety", s)),
+		}
+	}
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Boosts {
+	pub attack_potion:   Potion,
+	pub strength_potion: Potion,
+	pub prayer:          Prayer,
+	// how far below full hitpoints the player fights, only dharok's cares
+	pub hitpoints_lost:  RunescapeInt,
+	pub specials:        Specials,
+}
+
+impl Default for Boosts {
+	fn default() -> Self {
+		Self {
+			attack_potion:   Potion::None,
+			strength_potion: Potion::None,
+			prayer:          Prayer::None,
+			hitpoints_lost:  0,
+			specials:        Specials::default(),
+		}
+	}
+}
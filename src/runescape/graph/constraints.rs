@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+
+use crate::runescape::RunescapeInt;
+use crate::runescape::graph::{combat_level, level_to_xp, xp_to_level};
+use crate::runescape::graph::level::Melee;
+use crate::runescape::hiscores::Skill;
+
+#[derive(Debug, Clone, Default)]
+pub struct BuildConstraints {
+	caps: BTreeMap<Skill, RunescapeInt>,
+	max_combat: Option<RunescapeInt>,
+}
+
+impl BuildConstraints {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn pure() -> Self {
+		Self::new().cap(Skill::Defence, 1)
+	}
+
+	pub fn zerker() -> Self {
+		Self::new().cap(Skill::Defence, 45)
+	}
+
+	pub fn cap(mut self, skill: Skill, level: RunescapeInt) -> Self {
+		self.caps.insert(skill, level);
+		self
+	}
+
+	pub fn max_combat(mut self, level: RunescapeInt) -> Self {
+		self.max_combat = Some(level);
+		self
+	}
+
+	// a capped skill may not gain any xp once it would go past its cap, so
+	// neither may a style that trains it
+	pub fn allows(&self, from: &Melee, to: &Melee) -> bool {
+		for (skill, cap) in &self.caps {
+			if to.xp(*skill) > from.xp(*skill) && to.level(*skill) > *cap {
+				return false;
+			}
+		}
+		if let Some(max_combat) = self.max_combat {
+			let combat = to.combat_level();
+			if combat > max_combat && combat > from.combat_level() {
+				return false;
+			}
+		}
+		true
+	}
+
+	// the goal can't ask for more than the build allows, or for less than we
+	// already have. past a combat cap the highest level comes down first
+	pub fn limit_goal(&self, start: &Melee, goal: &Melee) -> Melee {
+		let level = |skill| {
+			let level = goal.level(skill);
+			let capped = self.caps.get(&skill).map_or(level, |cap| level.min(*cap));
+			capped.max(start.level(skill))
+		};
+		let skills = [Skill::Attack, Skill::Strength, Skill::Defence];
+		let mut levels = [level(Skill::Attack), level(Skill::Strength), level(Skill::Defence)];
+		if let Some(max_combat) = self.max_combat {
+			while goal_combat(start, &skills, &levels) > max_combat {
+				let highest = (0..levels.len())
+					.filter(|i| levels[*i] > start.level(skills[*i]))
+					.max_by_key(|i| levels[*i]);
+				match highest {
+					Some(i) => levels[i] -= 1,
+					None    => break,
+				}
+			}
+		}
+		Melee::new(levels[0], levels[1], levels[2], None)
+	}
+}
+
+// the combat level reached by training from `start` up to `levels`,
+// hitpoints included
+fn goal_combat(start: &Melee, skills: &[Skill], levels: &[RunescapeInt]) -> RunescapeInt {
+	let gained_xp: u64 = skills.iter().zip(levels)
+		.map(|(skill, level)| level_to_xp(*level).saturating_sub(start.xp(*skill)))
+		.sum();
+	let hitpoints = xp_to_level(start.xp(Skill::Hitpoints) + gained_xp / 3);
+	combat_level(
+		levels[0], levels[1], levels[2], hitpoints,
+		start.level(Skill::Prayer), start.level(Skill::Ranged), start.level(Skill::Magic),
+	)
+}
+
+impl std::str::FromStr for BuildConstraints {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"main"   => Ok(BuildConstraints::new()),
+			"pure"   => Ok(BuildConstraints::pure()),
+			"zerker" => Ok(BuildConstraints::zerker()),
+			_        => Err(format!("unknown build {}, expected main, pure or zerker", s)),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn goal_fits_under_the_combat_cap() {
+		let start = Melee::new(40, 40, 40, None);
+		let constraints = BuildConstraints::new().max_combat(60);
+		let goal = constraints.limit_goal(&start, &Melee::new(70, 70, 70, None));
+		let levels = [goal.level(Skill::Attack), goal.level(Skill::Strength), goal.level(Skill::Defence)];
+		assert!(goal_combat(&start, &[Skill::Attack, Skill::Strength, Skill::Defence], &levels) <= 60);
+		// one more level in anything would go over
+		for i in 0..levels.len() {
+			let mut more = levels;
+			more[i] += 1;
+			assert!(goal_combat(&start, &[Skill::Attack, Skill::Strength, Skill::Defence], &more) > 60);
+		}
+	}
+
+	#[test]
+	fn goal_never_drops_below_the_start() {
+		let start = Melee::new(60, 60, 60, None);
+		let goal = BuildConstraints::new().max_combat(3).limit_goal(&start, &Melee::new(70, 70, 70, None));
+		assert!(goal.same_levels(&start), "{} {}", goal, start);
+	}
+
+	#[test]
+	fn goal_at_the_combat_cap_is_kept() {
+		let start = Melee::new(40, 40, 40, None);
+		let skills = [Skill::Attack, Skill::Strength, Skill::Defence];
+		let cap = goal_combat(&start, &skills, &[60, 60, 50]);
+		let goal = BuildConstraints::new().max_combat(cap).limit_goal(&start, &Melee::new(60, 60, 50, None));
+		assert_eq!(goal, Melee::new(60, 60, 50, None), "{}", goal);
+		// and one level under it the highest skill comes down first
+		let goal = BuildConstraints::new().max_combat(cap - 1).limit_goal(&start, &Melee::new(60, 60, 50, None));
+		assert!(goal.level(Skill::Attack) < 60 || goal.level(Skill::Strength) < 60, "{}", goal);
+		assert_eq!(goal.level(Skill::Defence), 50);
+	}
+}
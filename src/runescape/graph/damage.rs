@@ -0,0 +1,187 @@
+use crate::runescape::RunescapeInt;
+
+// chance of dealing each amount of damage with one attack, indexed by damage
+#[derive(Debug, Clone, PartialEq)]
+pub struct HitDistribution {
+	probabilities: Vec<f64>,
+}
+
+impl HitDistribution {
+	// an attack that never does damage
+	pub fn zero() -> Self {
+		Self { probabilities: vec![1.0] }
+	}
+
+	// a normal attack roll: hits with `hit_chance` for a uniform 0..=max_hit
+	pub fn uniform(hit_chance: f64, max_hit: RunescapeInt) -> Self {
+		Self::uniform_with_min(hit_chance, 0, max_hit)
+	}
+
+	// a hit that lands always does at least `min_hit`, a miss still does nothing
+	pub fn uniform_with_min(hit_chance: f64, min_hit: RunescapeInt, max_hit: RunescapeInt) -> Self {
+		let max_hit = max_hit.max(0) as usize;
+		let min_hit = (min_hit.max(0) as usize).min(max_hit);
+		let damage_chance = hit_chance / (max_hit - min_hit + 1) as f64;
+		let mut probabilities = vec![0.0; max_hit + 1];
+		probabilities[0] = 1.0 - hit_chance;
+		for probability in &mut probabilities[min_hit..=max_hit] {
+			*probability += damage_chance;
+		}
+		Self { probabilities }
+	}
+
+	// several hits landing as one attack, like a multi-hit weapon
+	pub fn multi_hit(hits: &[HitDistribution]) -> Self {
+		hits.iter().fold(Self::zero(), |total, hit| total.convolve(hit))
+	}
+
+	// one of several attacks, each picked with its weight. the weights add up to 1
+	pub fn blend(parts: &[(f64, HitDistribution)]) -> Self {
+		let len = parts.iter().map(|(_, hit)| hit.probabilities.len()).max().unwrap_or(1);
+		let mut probabilities = vec![0.0; len];
+		for (weight, hit) in parts {
+			for (damage, probability) in hit.probabilities.iter().enumerate() {
+				probabilities[damage] += weight * probability;
+			}
+		}
+		Self { probabilities }
+	}
+
+	// the damage of this attack and `other` added together
+	pub fn convolve(&self, other: &Self) -> Self {
+		let mut probabilities = vec![0.0; self.probabilities.len() + other.probabilities.len() - 1];
+		for (a, pa) in self.probabilities.iter().enumerate() {
+			for (b, pb) in other.probabilities.iter().enumerate() {
+				probabilities[a + b] += pa * pb;
+			}
+		}
+		Self { probabilities }
+	}
+
+	// the total damage of `count` of these attacks
+	pub fn repeat(&self, count: usize) -> Self {
+		(0..count).fold(Self::zero(), |total, _| total.convolve(self))
+	}
+
+	// anything above `cap` only deals `cap`
+	pub fn capped(&self, cap: RunescapeInt) -> Self {
+		let cap = cap.max(0) as usize;
+		if cap >= self.max_hit() as usize {
+			return self.clone();
+		}
+		let mut probabilities = self.probabilities[..=cap].to_vec();
+		probabilities[cap] += self.probabilities[cap + 1..].iter().sum::<f64>();
+		Self { probabilities }
+	}
+
+	pub fn max_hit(&self) -> RunescapeInt {
+		(self.probabilities.len() - 1) as RunescapeInt
+	}
+
+	pub fn probability(&self, damage: RunescapeInt) -> f64 {
+		if damage < 0 {
+			return 0.0;
+		}
+		self.probabilities.get(damage as usize).copied().unwrap_or(0.0)
+	}
+
+	// chance of dealing at most `damage`
+	pub fn cdf(&self, damage: RunescapeInt) -> f64 {
+		if damage < 0 {
+			return 0.0;
+		}
+		self.probabilities.iter().take(damage as usize + 1).sum()
+	}
+
+	// the damage at which `cdf` first reaches `u`, turns a uniform 0..1 roll into a hit
+	pub fn inverse_cdf(&self, u: f64) -> RunescapeInt {
+		let mut cumulative = 0.0;
+		for (damage, probability) in self.iter() {
+			cumulative += probability;
+			if u < cumulative {
+				return damage;
+			}
+		}
+		self.max_hit()
+	}
+
+	pub fn mean(&self) -> f64 {
+		self.probabilities.iter()
+			.enumerate()
+			.map(|(damage, probability)| damage as f64 * probability)
+			.sum()
+	}
+
+	pub fn variance(&self) -> f64 {
+		let mean = self.mean();
+		self.probabilities.iter()
+			.enumerate()
+			.map(|(damage, probability)| (damage as f64 - mean).powi(2) * probability)
+			.sum()
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item=(RunescapeInt, f64)> + '_ {
+		self.probabilities.iter().enumerate().map(|(damage, probability)| (damage as RunescapeInt, *probability))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn close(a: f64, b: f64) -> bool {
+		(a - b).abs() < 1e-9
+	}
+
+	fn total(hit: &HitDistribution) -> f64 {
+		hit.iter().map(|(_, probability)| probability).sum()
+	}
+
+	#[test]
+	fn uniform_mean_and_variance() {
+		// a sure hit on 0..=10 is a discrete uniform: mean 5, variance (11^2 - 1) / 12
+		let hit = HitDistribution::uniform(1.0, 10);
+		assert!(close(hit.mean(), 5.0));
+		assert!(close(hit.variance(), 10.0));
+		// a miss is a 0, so half the hits halve the mean
+		let half = HitDistribution::uniform(0.5, 10);
+		assert!(close(half.mean(), 2.5));
+		assert!(close(half.probability(0), 0.5 + 0.5 / 11.0));
+		assert!(close(total(&half), 1.0));
+	}
+
+	#[test]
+	fn convolution_adds_up() {
+		let a = HitDistribution::uniform(0.7, 12);
+		let b = HitDistribution::uniform_with_min(0.4, 3, 5);
+		let both = a.convolve(&b);
+		assert!(close(total(&both), 1.0));
+		assert_eq!(both.max_hit(), 17);
+		assert!(close(both.mean(), a.mean() + b.mean()));
+		// independent hits: the variances add too
+		assert!(close(both.variance(), a.variance() + b.variance()));
+		assert_eq!(HitDistribution::multi_hit(&[a.clone(), b.clone()]), both);
+		assert!(close(total(&a.repeat(3)), 1.0));
+	}
+
+	#[test]
+	fn capped_moves_the_tail_onto_the_cap() {
+		let hit = HitDistribution::uniform(1.0, 9);
+		let capped = hit.capped(4);
+		assert_eq!(capped.max_hit(), 4);
+		assert!(close(capped.probability(3), 0.1));
+		assert!(close(capped.probability(4), 0.6));
+		assert!(close(total(&capped), 1.0));
+		assert_eq!(hit.capped(20), hit);
+	}
+
+	#[test]
+	fn cdf_reaches_one_at_the_max_hit() {
+		let hit = HitDistribution::uniform(0.8, 15).convolve(&HitDistribution::uniform(0.3, 4));
+		assert!(close(hit.cdf(hit.max_hit()), 1.0));
+		assert!(close(hit.cdf(-1), 0.0));
+		assert!(close(hit.cdf(0), hit.probability(0)));
+		assert_eq!(hit.inverse_cdf(0.0), 0);
+		assert_eq!(hit.inverse_cdf(0.999_999_999_9), hit.max_hit());
+	}
+}
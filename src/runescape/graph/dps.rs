@@ -0,0 +1,159 @@
+use crate::runescape::RunescapeInt;
+use crate::runescape::gear::item_group::{defence_bonus, ItemGroup};
+use crate::runescape::gear::passives::Passive;
+use crate::runescape::gear::sets::SetPiece;
+use crate::runescape::gear::special::{ReducedDefence, SpecialAttack};
+use crate::runescape::graph::kill::KillModel;
+use crate::runescape::graph::level::{stance_bonus, Melee};
+use crate::runescape::graph::upkeep::DamageTaken;
+use crate::runescape::monster::Target;
+use crate::runescape::osrsbox_db::types::*;
+
+// an explicit set of worn items, at most one per slot
+#[derive(Debug, Clone)]
+pub struct Loadout<'a> {
+	items: Vec<&'a Item>,
+}
+
+impl<'a> Loadout<'a> {
+	pub fn new(items: Vec<&'a Item>) -> Result<Self, String> {
+		for (i, item) in items.iter().enumerate() {
+			let slot = item.equipment.slot.worn_in();
+			if let Some(other) = items[..i].iter().find(|other| other.equipment.slot.worn_in() == slot) {
+				return Err(format!("{} and {} are both worn in the {:?} slot", other.name, item.name, slot));
+			}
+		}
+		let two_handed = items.iter().find(|item| item.equipment.slot == Slot::TwoH);
+		let shield = items.iter().find(|item| item.equipment.slot == Slot::Shield);
+		if let (Some(weapon), Some(shield)) = (two_handed, shield) {
+			return Err(format!("{} is two-handed, it can't be worn with {}", weapon.name, shield.name));
+		}
+		Ok(Self { items })
+	}
+
+	pub fn items(&self) -> &[&'a Item] {
+		&self.items
+	}
+
+	pub fn weapon(&self) -> Option<&'a Item> {
+		self.items.iter().find(|item| item.weapon.is_some()).copied()
+	}
+
+	// one group per item, so the loadout can go through the same math as the planner
+	pub fn item_groups(&self, attack_type: AttackType, attack_style: AttackStyle, defend_against: Option<AttackType>) -> Vec<ItemGroup> {
+		self.items.iter().map(|item| {
+			ItemGroup {
+				item_ids: vec![item.id],
+				attack_value: match attack_type {
+					AttackType::Stab  => item.equipment.attack_stab,
+					AttackType::Slash => item.equipment.attack_slash,
+					AttackType::Crush => item.equipment.attack_crush,
+					_                 => 0,
+				},
+				strength_value: item.equipment.melee_strength,
+				defence_value: defend_against.map_or(0, |against| defence_bonus(&item.equipment, against)),
+				prayer_value: item.equipment.prayer,
+				cost: item.cost,
+				attack_type,
+				attack_style: item.weapon.as_ref().map(|_| attack_style),
+				attack_speed: item.weapon.as_ref().map(|weapon| {
+					weapon.attack_speed + weapon.stance(attack_type, attack_style).map_or(0, |stance| stance.speed)
+				}),
+				style_bonus: item.weapon.as_ref()
+					.and_then(|weapon| weapon.stance(attack_type, attack_style))
+					.map_or(StyleBonus::default(), |stance| stance.style_bonus),
+				two_handed: item.equipment.slot == Slot::TwoH,
+				set_piece: SetPiece::of(&item.name),
+				passive: Passive::of(&item.name),
+				special: SpecialAttack::of(&item.name),
+			}
+		}).collect()
+	}
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DpsReport {
+	pub max_hit: RunescapeInt,
+	pub max_attack_roll: RunescapeInt,
+	pub max_defence_roll: RunescapeInt,
+	pub accuracy: f64,
+	pub attack_speed: RunescapeInt,
+	pub dps: f64,
+	pub xp_per_hour: f64,
+	// the skills the xp goes to
+	pub xp: XpSplit,
+	pub seconds_to_kill: f64,
+	pub damage: Option<DamageTaken>,
+	pub specials_per_hour: Option<(SpecialAttack, f64)>,
+	// the target's average defence level once it's been reduced and the share
+	// of kills the special bar pays for that, if it was
+	pub reduced_defence: Option<(f64, f64)>,
+}
+
+pub fn calculate(player: &Melee, attack_style: AttackStyle, attack_type: AttackType, loadout: &Loadout, target: &Target, kill_model: &KillModel) -> Result<DpsReport, String> {
+	let weapon = loadout.weapon().ok_or("the loadout has no weapon, add unarmed to fight without one")?;
+	let stance = weapon.weapon.as_ref().unwrap().stance(attack_type, attack_style)
+		.ok_or_else(|| format!("{} has no {:?} {:?} stance", weapon.name, attack_style, attack_type))?;
+
+	let groups = loadout.item_groups(attack_type, attack_style, target.attack.map(|attack| attack.attack_type));
+	let attack_bonus = groups.iter().map(|group| group.attack_value).sum();
+	let strength_bonus = groups.iter().map(|group| group.strength_value).sum();
+	let max_defence_roll = target.max_defence_roll(attack_type);
+	let unreduced = ReducedDefence::none(target.defence_level);
+	let (_, attack_speed) = player.attack(attack_style, &groups, target, &unreduced);
+	let kills = player.kill_stats(attack_style, &groups, target, kill_model);
+	let bonus = player.gear_bonus(&groups, target);
+	let style_bonus = stance_bonus(attack_style, &groups);
+	let reduced = player.defence_reductions(attack_style, &groups, target);
+	// the reductions only help on the kills the special bar paid for
+	let reduced_share = kills.reduced_share.unwrap_or(0.0);
+	let accuracy = |reduced| player.average_hit_chance(style_bonus, attack_bonus, attack_type, target, reduced, &bonus);
+
+	Ok(DpsReport {
+		max_hit: player.max_hit(style_bonus, strength_bonus, &bonus),
+		max_attack_roll: player.max_attack_roll(style_bonus, attack_bonus, &bonus),
+		max_defence_roll,
+		accuracy: reduced_share * accuracy(&reduced) + (1.0 - reduced_share) * accuracy(&unreduced),
+		attack_speed,
+		dps: player.dps(attack_style, &groups, target, kill_model),
+		xp_per_hour: target.xp_per_kill() * kills.kills_per_hour,
+		xp: stance.xp,
+		seconds_to_kill: kills.seconds_to_kill,
+		damage: kills.damage,
+		specials_per_hour: player.specials_per_hour(attack_style, &groups, target, kill_model),
+		reduced_defence: kills.reduced_share.map(|share| (reduced.average(), share)),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::BTreeMap;
+
+	const WEAPONS: &str = include_str!("../../../tests/fixtures/osrsbox/items-weapon.json");
+
+	fn close(a: f64, b: f64) -> bool {
+		(a - b).abs() < 1e-9
+	}
+
+	#[test]
+	fn dragon_scimitar_by_hand() {
+		let items: BTreeMap<String, Item> = serde_json::from_str(WEAPONS).unwrap();
+		let loadout = Loadout::new(vec![&items["4587"]]).unwrap();
+		let player = Melee::new(70, 70, 70, None);
+		let target = Target { defence_level: 50, defence_slash: 20, ..Target::sand_crab() };
+		let report = calculate(&player, AttackStyle::Aggressive, AttackType::Slash, &loadout, &target, &KillModel::default()).unwrap();
+
+		// 70 + 3 aggressive + 8 is 81 effective strength: 0.5 + 81 * (66 + 64) / 640
+		assert_eq!(report.max_hit, 16);
+		// (70 + 8) * (67 + 64) against (50 + 9) * (20 + 64)
+		assert_eq!(report.max_attack_roll, 10218);
+		assert_eq!(report.max_defence_roll, 4956);
+		assert!(close(report.accuracy, 1.0 - 4958.0 / (2.0 * 10219.0)), "{}", report.accuracy);
+		assert_eq!(report.attack_speed, 4);
+		assert_eq!(report.xp.skills(), &[crate::runescape::hiscores::Skill::Strength]);
+
+		// there's no lunge in the aggressive style
+		assert!(calculate(&player, AttackStyle::Aggressive, AttackType::Stab, &loadout, &target, &KillModel::default()).is_err());
+	}
+}
@@ -0,0 +1,148 @@
+use crate::runescape::RunescapeInt;
+use crate::runescape::graph::damage::HitDistribution;
+use crate::runescape::graph::upkeep::{DamageTaken, Upkeep};
+
+// aggressive targets stop attacking after ten minutes near them
+const AGGRESSION_SECONDS: f64 = 10.0 * 60.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct KillModel {
+	// seconds between a target dying and it being attackable again
+	pub respawn_seconds: f64,
+	// how many targets can be fought one after another
+	pub targets: RunescapeInt,
+	// seconds spent every ten minutes walking off so the targets attack again
+	pub aggression_reset_seconds: f64,
+	// food and banking, only used against targets that fight back
+	pub upkeep: Upkeep,
+}
+
+impl Default for KillModel {
+	fn default() -> Self {
+		Self {
+			respawn_seconds: 0.0,
+			targets: 1,
+			aggression_reset_seconds: 0.0,
+			upkeep: Upkeep::default(),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct KillStats {
+	// the naive damage per second, overkill included
+	pub dps: f64,
+	pub attacks_to_kill: f64,
+	pub seconds_to_kill: f64,
+	pub kills_per_hour: f64,
+	pub damage: Option<DamageTaken>,
+	// the share of kills the special bar paid defence reductions for, if any were asked for
+	pub reduced_share: Option<f64>,
+}
+
+// expected number of attacks to bring `hitpoints` to zero. damage past the
+// last point of hitpoints is wasted, which the mean damage doesn't account for
+pub fn expected_attacks_to_kill(hit: &HitDistribution, hitpoints: RunescapeInt) -> f64 {
+	let hitpoints = hitpoints.max(0) as usize;
+	let miss_chance = hit.probability(0);
+	if miss_chance >= 1.0 {
+		return if hitpoints == 0 { 0.0 } else { f64::INFINITY };
+	}
+
+	// expected[hp] is the expected number of attacks left with hp remaining
+	let mut expected = vec![0.0; hitpoints + 1];
+	for hp in 1..=hitpoints {
+		let mut sum = 1.0;
+		for damage in 1..hp.min(hit.max_hit() as usize + 1) {
+			sum += hit.probability(damage as RunescapeInt) * expected[hp - damage];
+		}
+		expected[hp] = sum / (1.0 - miss_chance);
+	}
+	expected[hitpoints]
+}
+
+// kills where `share` of them go like `some` and the rest like `others`,
+// the time a kill takes is what averages out
+pub fn blend_kills(share: f64, some: &KillStats, others: &KillStats) -> KillStats {
+	let blend = |a: f64, b: f64| share * a + (1.0 - share) * b;
+	KillStats {
+		dps: blend(some.dps, others.dps),
+		attacks_to_kill: blend(some.attacks_to_kill, others.attacks_to_kill),
+		seconds_to_kill: blend(some.seconds_to_kill, others.seconds_to_kill),
+		kills_per_hour: 1.0 / blend(1.0 / some.kills_per_hour, 1.0 / others.kills_per_hour),
+		damage: None,
+		reduced_share: None,
+	}
+}
+
+impl KillModel {
+	pub fn kill_stats(&self, dps: f64, attacks_to_kill: f64, attack_seconds: f64) -> KillStats {
+		let seconds_to_kill = attacks_to_kill * attack_seconds;
+		// every target spends seconds_to_kill dying and respawn_seconds respawning,
+		// so several targets can hide the respawn but never the kill itself
+		let targets = self.targets.max(1) as f64;
+		let seconds_per_kill = seconds_to_kill.max((seconds_to_kill + self.respawn_seconds) / targets);
+		KillStats {
+			dps,
+			attacks_to_kill,
+			seconds_to_kill,
+			kills_per_hour: 60.0 * 60.0 / seconds_per_kill * self.aggression_uptime(),
+			damage: None,
+			reduced_share: None,
+		}
+	}
+
+	// the part of the time spent near the targets rather than resetting them
+	pub fn aggression_uptime(&self) -> f64 {
+		AGGRESSION_SECONDS / (AGGRESSION_SECONDS + self.aggression_reset_seconds.max(0.0))
+	}
+
+	// the target only hits back while it's being killed, and the time spent
+	// eating and banking comes out of the kills
+	pub fn with_damage_taken(&self, kills: KillStats, incoming_dps: f64) -> KillStats {
+		let combat_seconds = (kills.seconds_to_kill * kills.kills_per_hour).min(60.0 * 60.0);
+		let damage = self.upkeep.damage_taken(incoming_dps, combat_seconds);
+		KillStats {
+			kills_per_hour: kills.kills_per_hour * (1.0 - damage.downtime),
+			damage: Some(damage),
+			..kills
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn close(a: f64, b: f64) -> bool {
+		(a - b).abs() < 1e-9
+	}
+
+	#[test]
+	fn a_hit_that_always_kills_takes_one_attack() {
+		assert!(close(expected_attacks_to_kill(&HitDistribution::uniform_with_min(1.0, 10, 10), 10), 1.0));
+		assert!(close(expected_attacks_to_kill(&HitDistribution::uniform_with_min(1.0, 1, 20), 1), 1.0));
+		assert!(close(expected_attacks_to_kill(&HitDistribution::uniform(1.0, 20), 0), 0.0));
+	}
+
+	#[test]
+	fn overkill_is_wasted() {
+		// 2 damage a hit against 3 hitpoints still takes two hits, not 1.5
+		assert!(close(expected_attacks_to_kill(&HitDistribution::uniform_with_min(1.0, 2, 2), 3), 2.0));
+	}
+
+	#[test]
+	fn hits_of_zero_take_extra_attacks() {
+		// a 0 or a 1 with even chance: two attacks a point of hitpoints
+		let hit = HitDistribution::uniform(1.0, 1);
+		assert!(close(expected_attacks_to_kill(&hit, 1), 2.0));
+		assert!(close(expected_attacks_to_kill(&hit, 2), 4.0));
+		// 0, 1 or 2 against 2 hitpoints: E[1] = 1.5, E[2] = (1 + E[1] / 3) / (2 / 3)
+		assert!(close(expected_attacks_to_kill(&HitDistribution::uniform(1.0, 2), 2), 2.25));
+	}
+
+	#[test]
+	fn never_hitting_never_kills() {
+		assert_eq!(expected_attacks_to_kill(&HitDistribution::zero(), 5), f64::INFINITY);
+	}
+}
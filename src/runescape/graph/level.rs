@@ -1,32 +1,77 @@
-use crate::runescape::gear::item_group::filter_elided_items;
-use crate::runescape::gear::GearCache;
 
+use crate::runescape::gear::breakpoints::Breakpoint;
 use crate::runescape::gear::item_group::ItemGroup;
+use crate::runescape::gear::bonus::GearBonus;
+use crate::runescape::gear::sets::{set_search, FULL_SETS};
+use crate::runescape::gear::special::{DefenceReduction, ReducedDefence, SpecialAttack, SpecialEnergy};
 use crate::runescape::RunescapeInt;
+use crate::runescape::graph::{combat_level, level_to_xp, xp_to_level};
+use crate::runescape::graph::boosts::Boosts;
+use crate::runescape::graph::damage::HitDistribution;
+use crate::runescape::graph::kill::{blend_kills, expected_attacks_to_kill, KillModel, KillStats};
+use crate::runescape::graph::planner::Planner;
+use crate::runescape::graph::spots::TrainingSpot;
+use crate::runescape::hiscores::{Hiscores, Skill};
+use crate::runescape::monster::{Attribute, MonsterAttack, Target};
 use crate::runescape::osrsbox_db::types::*;
+use std::collections::BTreeMap;
 use std::hash::{Hash, Hasher};
 use ordered_float::NotNan;
 
 use itertools::Itertools;
 
-#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct Level {
 	value: RunescapeInt,
+	xp: u64,
 }
 
-const CRAB_MAX_DEFENCE_ROLL: RunescapeInt = (1 + 1 + 8) * (0 + 64);
-const GAME_TICK: f64 = 0.6;
+pub const GAME_TICK: f64 = 0.6;
+// the order gear is searched and listed in, the weapon has to come first
+const SLOTS: [Slot; 11] = [
+	Slot::Weapon, Slot::Ammo, Slot::Head, Slot::Cape, Slot::Neck, Slot::Body,
+	Slot::Legs, Slot::Shield, Slot::Hands, Slot::Feet, Slot::Ring,
+];
+const MELEE_ATTACK_TYPES: [AttackType; 3] = [AttackType::Stab, AttackType::Slash, AttackType::Crush];
+// a set without a weapon punches
+const UNARMED_SPEED: RunescapeInt = 4;
+const BASE_HITPOINTS_XP: u64 = 1_154;
 
 impl Level {
+	pub fn new(value: RunescapeInt) -> Self {
+		Self { value, xp: level_to_xp(value) }
+	}
+
+	pub fn from_xp(xp: u64) -> Self {
+		Self { value: xp_to_level(xp), xp }
+	}
+
 	pub fn xp_to_next_level(&self) -> u64 {
 		match self.value {
-			1..=126 => super::XP_TABLE[self.value as usize] - super::XP_TABLE[self.value as usize - 1],
+			1..=126 => super::XP_TABLE[self.value as usize] - self.xp,
 			127     => 0,
 			_       => 0xFFFFFFFFFFFFFFFF
 		}
 	}
 }
 
+// the xp is part of the state. successors only leave whole levels, so
+// partial xp is only ever left over from the start
+impl PartialEq for Level {
+	fn eq(&self, other: &Self) -> bool {
+		self.value == other.value && self.xp == other.xp
+	}
+}
+
+impl Hash for Level {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.value.hash(state);
+		self.xp.hash(state);
+	}
+}
+
+impl Eq for Level {}
+
 // #[derive(Debug, Clone, Copy, PartialEq)]
 // enum Direction {
 // 	Attack,
@@ -39,64 +84,180 @@ pub struct Melee {
 	attack: Level,
 	strength: Level,
 	defence: Level,
+	hitpoints: Level,
+	prayer: RunescapeInt,
+	ranged: RunescapeInt,
+	magic: RunescapeInt,
+	boosts: Boosts,
 	pub gear_that_got_us_here: Option<Vec<ItemGroup>>,
+	pub kills_that_got_us_here: Option<KillStats>,
+	// index into the planner's spots
+	pub spot_that_got_us_here: Option<usize>,
+	pub style_that_got_us_here: Option<AttackStyle>,
 }
 
 impl Melee {
 	pub fn new(attack: RunescapeInt, strength: RunescapeInt, defence: RunescapeInt, gear: Option<Vec<ItemGroup>>) -> Self {
+		let combat_xp = level_to_xp(attack) + level_to_xp(strength) + level_to_xp(defence);
 		Self {
-			attack: Level { value: attack },
-			strength: Level { value: strength },
-			defence: Level { value: defence },
+			attack: Level::new(attack),
+			strength: Level::new(strength),
+			defence: Level::new(defence),
+			hitpoints: Level::from_xp(BASE_HITPOINTS_XP + combat_xp / 3),
+			prayer: 1,
+			ranged: 1,
+			magic: 1,
+			boosts: Boosts::default(),
 			gear_that_got_us_here: gear,
+			kills_that_got_us_here: None,
+			spot_that_got_us_here: None,
+			style_that_got_us_here: None,
 		}
 	}
 
-	fn effective_strength(&self, style: AttackStyle) -> RunescapeInt {
-		use AttackStyle::*;
-
-		let strength_level    = self.strength.value.min(99) as f64;
-		let potion_effect     = 0.0;
-		let prayer_multiplyer = 1.0;
-		let style_bonus = match style {
-			Aggressive => 3.0,
-			Controlled => 1.0,
-			_          => 0.0,
+	pub fn from_hiscores(hiscores: &Hiscores) -> Self {
+		let level = |skill| match hiscores.xp(skill) {
+			Some(xp) => Level::from_xp(xp),
+			None     => Level::new(hiscores.level(skill).unwrap_or(1)),
 		};
+		let attack = level(Skill::Attack);
+		let strength = level(Skill::Strength);
+		let defence = level(Skill::Defence);
+		// unranked hitpoints still start at 10
+		let hitpoints = match hiscores.xp(Skill::Hitpoints) {
+			Some(xp) => Level::from_xp(xp.max(BASE_HITPOINTS_XP)),
+			None     => Level::from_xp(BASE_HITPOINTS_XP + (attack.xp + strength.xp + defence.xp) / 3),
+		};
+		Self {
+			attack,
+			strength,
+			defence,
+			hitpoints,
+			prayer: hiscores.level(Skill::Prayer).unwrap_or(1),
+			ranged: hiscores.level(Skill::Ranged).unwrap_or(1),
+			magic: hiscores.level(Skill::Magic).unwrap_or(1),
+			boosts: Boosts::default(),
+			gear_that_got_us_here: None,
+			kills_that_got_us_here: None,
+			spot_that_got_us_here: None,
+			style_that_got_us_here: None,
+		}
+	}
 
-		((strength_level + potion_effect) * prayer_multiplyer + style_bonus + 8.0) as RunescapeInt
+	pub fn level(&self, skill: Skill) -> RunescapeInt {
+		match skill {
+			Skill::Attack    => self.attack.value,
+			Skill::Strength  => self.strength.value,
+			Skill::Defence   => self.defence.value,
+			Skill::Hitpoints => self.hitpoints.value,
+			Skill::Prayer    => self.prayer,
+			Skill::Ranged    => self.ranged,
+			Skill::Magic     => self.magic,
+			_                => 1,
+		}
 	}
 
-	fn effective_attack(&self, style: AttackStyle) -> RunescapeInt {
-		use AttackStyle::*;
+	// skills the planner doesn't train never gain xp, so the level's xp is good enough
+	pub fn xp(&self, skill: Skill) -> u64 {
+		match skill {
+			Skill::Attack    => self.attack.xp,
+			Skill::Strength  => self.strength.xp,
+			Skill::Defence   => self.defence.xp,
+			Skill::Hitpoints => self.hitpoints.xp,
+			_                => level_to_xp(self.level(skill)),
+		}
+	}
 
-		let attack_level      = self.attack.value.min(99) as f64;
-		let potion_effect     = 0.0;
-		let prayer_multiplyer = 1.0;
-		let style_bonus = match style {
-			Accurate   => 3.0,
-			Controlled => 1.0,
-			_          => 0.0,
-		};
+	// every level gear can require, the untrained skills as they were at the start
+	pub fn levels(&self) -> Breakpoint {
+		Breakpoint {
+			attack:    self.attack.value,
+			strength:  self.strength.value,
+			defence:   self.defence.value,
+			hitpoints: self.hitpoints.value,
+			prayer:    self.prayer,
+			ranged:    self.ranged,
+			magic:     self.magic,
+		}
+	}
+
+	// the trained levels match, wherever they were trained
+	pub fn same_levels(&self, other: &Self) -> bool {
+		self.attack.value == other.attack.value &&
+		self.strength.value == other.strength.value &&
+		self.defence.value == other.defence.value
+	}
+
+	pub fn combat_level(&self) -> RunescapeInt {
+		combat_level(self.attack.value, self.strength.value, self.defence.value, self.hitpoints.value, self.prayer, self.ranged, self.magic)
+	}
+
+	pub fn boosts(&self) -> &Boosts {
+		&self.boosts
+	}
+
+	pub fn with_boosts(mut self, boosts: Boosts) -> Self {
+		self.boosts = boosts;
+		self
+	}
+
+	// one skill set to the start of `level`, the others as they were
+	pub fn with_level(mut self, skill: Skill, level: RunescapeInt) -> Self {
+		match skill {
+			Skill::Attack    => self.attack = Level::new(level),
+			Skill::Strength  => self.strength = Level::new(level),
+			Skill::Defence   => self.defence = Level::new(level),
+			Skill::Hitpoints => self.hitpoints = Level::new(level),
+			Skill::Prayer    => self.prayer = level,
+			Skill::Ranged    => self.ranged = level,
+			Skill::Magic     => self.magic = level,
+			_                => {},
+		}
+		self
+	}
+
+	fn effective_strength(&self, stance: StyleBonus, gear: &GearBonus) -> RunescapeInt {
+		let strength_level         = self.strength.value.min(99);
+		let potion_effect          = self.boosts.strength_potion.boost(strength_level);
+		let (_, prayer_multiplyer) = self.boosts.prayer.multipliers();
+		let style_bonus            = stance.strength as f64;
 
-		((attack_level + potion_effect) * prayer_multiplyer + style_bonus + 8.0) as RunescapeInt
+		((((strength_level + potion_effect) as f64 * prayer_multiplyer).floor() + style_bonus + 8.0) * gear.effective_level) as RunescapeInt
 	}
 
-	fn max_hit(&self, style: AttackStyle, bonus: RunescapeInt) -> RunescapeInt {
+	fn effective_attack(&self, stance: StyleBonus, gear: &GearBonus) -> RunescapeInt {
+		let attack_level           = self.attack.value.min(99);
+		let potion_effect          = self.boosts.attack_potion.boost(attack_level);
+		let (prayer_multiplyer, _) = self.boosts.prayer.multipliers();
+		let style_bonus            = stance.attack as f64;
+
+		((((attack_level + potion_effect) as f64 * prayer_multiplyer).floor() + style_bonus + 8.0) * gear.effective_level) as RunescapeInt
+	}
+
+	fn effective_defence(&self, stance: StyleBonus) -> RunescapeInt {
+		let defence_level     = self.defence.value.min(99);
+		let prayer_multiplyer = self.boosts.prayer.defence_multiplier();
+		let style_bonus       = stance.defence as f64;
+
+		((defence_level as f64 * prayer_multiplyer).floor() + style_bonus + 8.0) as RunescapeInt
+	}
+
+	pub fn max_hit(&self, stance: StyleBonus, bonus: RunescapeInt, gear: &GearBonus) -> RunescapeInt {
 		let base = 0.5;
-		let effective_strength = self.effective_strength(style) as f64;
+		let effective_strength = self.effective_strength(stance, gear) as f64;
 		let bonus = bonus as f64;
 
-		(base + effective_strength * (bonus + 64.0) / 640.0) as RunescapeInt
+		let max_hit = (base + effective_strength * (bonus + 64.0) / 640.0) as RunescapeInt;
+		(max_hit as f64 * gear.damage) as RunescapeInt
 	}
 
-	fn max_attack_roll(&self, style: AttackStyle, bonus: RunescapeInt) -> RunescapeInt {
-		let effective_attack = self.effective_attack(style);
-		effective_attack * (bonus + 64)
+	pub fn max_attack_roll(&self, stance: StyleBonus, bonus: RunescapeInt, gear: &GearBonus) -> RunescapeInt {
+		let effective_attack = self.effective_attack(stance, gear);
+		((effective_attack * (bonus + 64)) as f64 * gear.accuracy) as RunescapeInt
 	}
 
-	fn hit_chance(&self, style: AttackStyle, attack_bonus: RunescapeInt, enemy_max_defence_roll: RunescapeInt) -> f64 {
-		let max_attack_roll = self.max_attack_roll(style, attack_bonus) as f64;
+	pub fn hit_chance(&self, stance: StyleBonus, attack_bonus: RunescapeInt, enemy_max_defence_roll: RunescapeInt, gear: &GearBonus) -> f64 {
+		let max_attack_roll = self.max_attack_roll(stance, attack_bonus, gear) as f64;
 		let max_defence_roll = enemy_max_defence_roll as f64;
 		if max_attack_roll > max_defence_roll {
 			1.0 - (max_defence_roll + 2.0) / (2.0 * (max_attack_roll + 1.0))
@@ -105,100 +266,383 @@ impl Melee {
 		}
 	}
 
-	pub fn dps(&self, style: AttackStyle, (attack_bonus, strength_bonus): (RunescapeInt, RunescapeInt), attack_speed: RunescapeInt) -> f64 {
-		let max_hit = self.max_hit(style, strength_bonus) as f64;
-		let hit_chance = self.hit_chance(style, attack_bonus, CRAB_MAX_DEFENCE_ROLL);
-		let attack_interval = attack_speed as f64;
-		hit_chance * (max_hit / 2.0) / (attack_interval * GAME_TICK)
+	// the target's defence after the reductions thrown at the start of a kill,
+	// each rolled with the worn gear and its special's own accuracy and damage
+	pub fn defence_reductions(&self, style: AttackStyle, items: &[ItemGroup], target: &Target) -> ReducedDefence {
+		let mut reduced = ReducedDefence::none(target.defence_level);
+		let demon = target.is(Attribute::Demon);
+		for reduction in &target.defence_reductions {
+			let mut levels = BTreeMap::new();
+			for (level, chance) in &reduced.levels {
+				let (hit_chance, max_hit) = self.reduction_roll(style, items, target, *reduction, *level);
+				reduced.damage += chance * reduction.special().hits(hit_chance, max_hit).mean();
+				*levels.entry(*level).or_insert(0.0) += chance * (1.0 - hit_chance);
+				// a hit that lands for 0 still lowers defence, except the godsword's
+				let damage_chance = hit_chance / (max_hit.max(0) + 1) as f64;
+				for damage in 0..=max_hit.max(0) {
+					*levels.entry(reduction.reduce(*level, target.defence_level, damage, demon)).or_insert(0.0) += chance * damage_chance;
+				}
+			}
+			reduced.levels = levels;
+			reduced.seconds += reduction.attack_speed() as f64 * GAME_TICK;
+			reduced.specials += 1;
+		}
+		reduced
+	}
+
+	// the chance one reduction lands against `level` defence, and its max hit
+	pub fn reduction_roll(&self, style: AttackStyle, items: &[ItemGroup], target: &Target, reduction: DefenceReduction, level: RunescapeInt) -> (f64, RunescapeInt) {
+		let (attack_bonus, strength_bonus) = sum_stats(items);
+		let stance = stance_bonus(style, items);
+		let gear = self.gear_bonus(items, target).with_special(reduction.special());
+		let hit_chance = self.hit_chance(stance, attack_bonus, target.defence_roll(level, find_attack_type(items)), &gear);
+		(hit_chance, self.max_hit(stance, strength_bonus, &gear))
+	}
+
+	// the chance to hit averaged over every defence level the reductions can leave the target at
+	pub fn average_hit_chance(&self, stance: StyleBonus, attack_bonus: RunescapeInt, attack_type: AttackType, target: &Target, reduced: &ReducedDefence, gear: &GearBonus) -> f64 {
+		reduced.levels.iter()
+			.map(|(level, chance)| chance * self.hit_chance(stance, attack_bonus, target.defence_roll(*level, attack_type), gear))
+			.sum()
+	}
+
+	pub fn max_defence_roll(&self, stance: StyleBonus, bonus: RunescapeInt) -> RunescapeInt {
+		self.effective_defence(stance) * (bonus + 64)
+	}
+
+	// the target's damage per second against us, its hits are uniform up to its max hit
+	pub fn incoming_dps(&self, stance: StyleBonus, defence_bonus: RunescapeInt, attack: &MonsterAttack, gear: &GearBonus) -> f64 {
+		let max_attack_roll = attack.max_attack_roll() as f64;
+		let max_defence_roll = self.max_defence_roll(stance, defence_bonus) as f64;
+		let hit_chance = if max_attack_roll > max_defence_roll {
+			1.0 - (max_defence_roll + 2.0) / (2.0 * (max_attack_roll + 1.0))
+		} else {
+			max_attack_roll / (2.0 * max_defence_roll + 1.0)
+		};
+		HitDistribution::uniform(hit_chance, attack.max_hit).mean() * gear.damage_taken / (attack.attack_speed as f64 * GAME_TICK)
+	}
+
+	pub fn hit_distribution(&self, stance: StyleBonus, (attack_bonus, strength_bonus): (RunescapeInt, RunescapeInt), attack_type: AttackType, target: &Target, reduced: &ReducedDefence, gear: &GearBonus) -> HitDistribution {
+		let max_hit = self.max_hit(stance, strength_bonus, gear);
+		let hit_chance = self.average_hit_chance(stance, attack_bonus, attack_type, target, reduced, gear);
+		HitDistribution::uniform(hit_chance, max_hit)
 	}
 
-	pub fn xp_per_hour(&self, style: AttackStyle, items: &Vec<ItemGroup>) -> f64 {
+	// the damage per second of one set of gear against `target`, with its set
+	// effects, passives, specials and the reductions thrown at it
+	pub fn dps(&self, style: AttackStyle, items: &[ItemGroup], target: &Target, kill_model: &KillModel) -> f64 {
+		self.kill_stats(style, items, target, kill_model).dps
+	}
+
+	// the set effects and passives of one set of gear against `target`
+	pub fn gear_bonus(&self, items: &[ItemGroup], target: &Target) -> GearBonus {
+		let defence_bonus = items.iter().map(|item| item.defence_value).sum();
+		GearBonus::of(items, find_attack_type(items), defence_bonus, self.hitpoints.value, self.boosts.hitpoints_lost, target)
+	}
+
+	// the hit distribution and attack speed of one set of gear against a target
+	// left at `reduced` defence, fighting unarmed when there's no weapon
+	pub fn attack(&self, style: AttackStyle, items: &[ItemGroup], target: &Target, reduced: &ReducedDefence) -> (HitDistribution, RunescapeInt) {
 		let stats = sum_stats(items);
-		let attack_speed = find_weapon_speed(items).expect("missing weapon");
+		let attack_speed = find_weapon_speed(items).unwrap_or(UNARMED_SPEED);
+		let attack_type = find_attack_type(items);
+		(self.hit_distribution(stance_bonus(style, items), stats, attack_type, target, reduced, &self.gear_bonus(items, target)), attack_speed)
+	}
 
-		(self.dps(style, stats, attack_speed) * 4.0) * (60.0 * 60.0)
+	// a special attack and the gear it's done in: its hits and attack speed. a
+	// swapped in weapon keeps the rest of the gear's attack bonus as it is for
+	// the worn weapon's attack type
+	pub fn special_attack(&self, style: AttackStyle, items: &[ItemGroup], target: &Target, reduced: &ReducedDefence) -> Option<(SpecialAttack, HitDistribution, RunescapeInt)> {
+		let specials = &self.boosts.specials;
+		if !specials.enabled {
+			return None;
+		}
+		let (special, (attack_bonus, strength_bonus), attack_type, attack_speed, gear) = match specials.swap {
+			Some(swap) => {
+				let rest: Vec<ItemGroup> = items.iter().filter(|item| item.attack_speed.is_none()).cloned().collect();
+				let (attack_bonus, strength_bonus) = sum_stats(&rest);
+				let stats = (attack_bonus + swap.attack_value, strength_bonus + swap.strength_value);
+				(swap.special, stats, swap.attack_type, swap.attack_speed, self.gear_bonus(&rest, target))
+			},
+			None => {
+				let weapon = items.iter().find(|item| item.attack_speed.is_some())?;
+				(weapon.special?, sum_stats(items), find_attack_type(items), weapon.attack_speed?, self.gear_bonus(items, target))
+			},
+		};
+		let gear = gear.with_special(special);
+		let stance = stance_bonus(style, items);
+		let hit_chance = self.average_hit_chance(stance, attack_bonus, attack_type, target, reduced, &gear);
+		let max_hit = self.max_hit(stance, strength_bonus, &gear);
+		Some((special, special.hits(hit_chance, max_hit), attack_speed))
 	}
 
-	fn hours_to_level(&self, style: AttackStyle, items: &Vec<ItemGroup>) -> f64 {
-		use AttackStyle::*;
+	// seconds one special attack takes, the granite maul's takes none
+	fn special_seconds(special: SpecialAttack, attack_speed: RunescapeInt) -> f64 {
+		if special.instant() { 0.0 } else { attack_speed as f64 * GAME_TICK }
+	}
 
-		let xp = match style {
-			Accurate   => self.attack.xp_to_next_level(),
-			Aggressive => self.strength.xp_to_next_level(),
-			Defensive  => self.defence.xp_to_next_level(),
-			_          => unreachable!(),
+	// every special `energy_per_second` pays for, as long as they fit in the hour
+	fn special_rate(special: SpecialAttack, attack_speed: RunescapeInt, energy_per_second: f64) -> f64 {
+		let hour = 60.0 * 60.0;
+		let specials = energy_per_second * hour / special.energy();
+		match Self::special_seconds(special, attack_speed) {
+			seconds if seconds > 0.0 => specials.min(hour / seconds),
+			_                        => specials,
+		}
+	}
+
+	pub fn specials_per_hour(&self, style: AttackStyle, items: &[ItemGroup], target: &Target, kill_model: &KillModel) -> Option<(SpecialAttack, f64)> {
+		let (special, _, attack_speed) = self.special_attack(style, items, target, &ReducedDefence::none(target.defence_level))?;
+		let (energy, _) = self.spend_energy(style, items, target, kill_model);
+		Some((special, Self::special_rate(special, attack_speed, energy.energy_per_second)))
+	}
+
+	// normal attacks blended with the special attacks `energy_per_second` pays
+	// for: the hit distribution of an average attack and how many seconds it takes
+	pub fn sustained_attack(&self, style: AttackStyle, items: &[ItemGroup], target: &Target, reduced: &ReducedDefence, energy_per_second: f64) -> (HitDistribution, f64) {
+		let (hit, attack_speed) = self.attack(style, items, target, reduced);
+		let attack_seconds = attack_speed as f64 * GAME_TICK;
+		let (special, special_hit, special_speed) = match self.special_attack(style, items, target, reduced) {
+			Some(special) => special,
+			None          => return (hit, attack_seconds),
 		};
 
-		(xp as f64) / self.xp_per_hour(style, items)
+		let hour = 60.0 * 60.0;
+		let specials = Self::special_rate(special, special_speed, energy_per_second);
+		let attacks = specials + (hour - specials * Self::special_seconds(special, special_speed)) / attack_seconds;
+		let share = specials / attacks;
+		(HitDistribution::blend(&[(1.0 - share, hit), (share, special_hit)]), hour / attacks)
 	}
 
-	pub fn successors(&self, gear_cache: &GearCache, goal: &Self) -> Vec<(Self, NotNan<f64>)> {
-		let mut v = Vec::with_capacity(3);
-		if let Some(successor) = self.successor(AttackStyle::Accurate,   gear_cache, goal) { v.push(successor) }
-		if let Some(successor) = self.successor(AttackStyle::Aggressive, gear_cache, goal) { v.push(successor) }
-		if let Some(successor) = self.successor(AttackStyle::Defensive,  gear_cache, goal) { v.push(successor) }
-		v
+	// the naive dps, the attacks and the seconds an attack it takes to kill a
+	// target thrown `reduced`, the reductions' damage counts towards the kill
+	fn attacks_to_kill(&self, style: AttackStyle, items: &[ItemGroup], target: &Target, reduced: &ReducedDefence, energy_per_second: f64) -> (f64, f64, f64) {
+		let (hit, attack_seconds) = self.sustained_attack(style, items, target, reduced, energy_per_second);
+		let hitpoints = (target.hitpoints as f64 - reduced.damage).round().max(0.0) as RunescapeInt;
+		let attacks = expected_attacks_to_kill(&hit, hitpoints);
+		let attacks_to_kill = attacks + reduced.specials as f64;
+		let seconds_per_attack = if attacks_to_kill > 0.0 { (attacks * attack_seconds + reduced.seconds) / attacks_to_kill } else { attack_seconds };
+		(hit.mean() / attack_seconds, attacks_to_kill, seconds_per_attack)
 	}
 
-	fn successor(&self, style: AttackStyle, gear_cache: &GearCache, goal: &Self) -> Option<(Self, NotNan<f64>)> {
-		let mut attack = self.attack.value;
-		let mut strength = self.strength.value;
-		let mut defence = self.defence.value;
+	// how the special bar is spent and the kills that gets. the reductions come
+	// first and are only thrown when the bar holds all of them, so they go on
+	// as many kills as regeneration pays for and the weapon's special gets
+	// whatever's left
+	fn spend_energy(&self, style: AttackStyle, items: &[ItemGroup], target: &Target, kill_model: &KillModel) -> (SpecialEnergy, KillStats) {
+		let energy = self.boosts.specials.energy_per_second();
+		let kills = |reduced: &ReducedDefence, energy_per_second: f64| {
+			let (dps, attacks, seconds) = self.attacks_to_kill(style, items, target, reduced, energy_per_second);
+			kill_model.kill_stats(dps, attacks, seconds)
+		};
+		let unreduced = ReducedDefence::none(target.defence_level);
+		if target.defence_reductions.is_empty() {
+			return (SpecialEnergy { reduced_share: 0.0, energy_per_second: energy }, kills(&unreduced, energy));
+		}
 
-		let breakpoint = gear_cache.get_breakpoint(attack, strength, defence);
+		let hour = 60.0 * 60.0;
+		let reduction_energy: f64 = target.defence_reductions.iter().map(|reduction| reduction.special().energy()).sum();
+		let reduced = self.defence_reductions(style, items, target);
+		let reduced_kills = kills(&reduced, 0.0);
+		let needed = reduced_kills.kills_per_hour * reduction_energy / hour;
+		if needed <= energy {
+			// a little generous, the weapon's special speeds up the kills and so
+			// the reductions with them
+			let left = energy - needed;
+			return (SpecialEnergy { reduced_share: 1.0, energy_per_second: left }, kills(&reduced, left));
+		}
+
+		// the reduced share of kills uses up exactly the energy regenerated over
+		// every kill: share * reduction_energy = share * per_kill(reduced) + (1 - share) * per_kill(plain)
+		let plain_kills = kills(&unreduced, 0.0);
+		let per_kill = |kills: &KillStats| energy * hour / kills.kills_per_hour;
+		let share = per_kill(&plain_kills) / (reduction_energy - per_kill(&reduced_kills) + per_kill(&plain_kills));
+		(SpecialEnergy { reduced_share: share, energy_per_second: 0.0 }, blend_kills(share, &reduced_kills, &plain_kills))
+	}
 
-		match style {
-			AttackStyle::Accurate   => { attack   += 1; if attack   > 127 || attack   > goal.attack.value   { return None } },
-			AttackStyle::Aggressive => { strength += 1; if strength > 127 || strength > goal.strength.value { return None } },
-			AttackStyle::Defensive  => { defence  += 1; if defence  > 127 || defence  > goal.defence.value  { return None } },
-			_                       => unreachable!(),
+	pub fn kill_stats(&self, style: AttackStyle, items: &[ItemGroup], target: &Target, kill_model: &KillModel) -> KillStats {
+		let (energy, kills) = self.spend_energy(style, items, target, kill_model);
+		let kills = KillStats {
+			reduced_share: if target.defence_reductions.is_empty() { None } else { Some(energy.reduced_share) },
+			..kills
 		};
+		match &target.attack {
+			Some(attack) => {
+				let defence_bonus = items.iter().map(|item| item.defence_value).sum();
+				kill_model.with_damage_taken(kills, self.incoming_dps(stance_bonus(style, items), defence_bonus, attack, &self.gear_bonus(items, target)))
+			},
+			None => kills,
+		}
+	}
 
-		let weapon = gear_cache.get_by_slot_full(Slot::Weapon, breakpoint, AttackType::Slash, style);
-		let ammo = gear_cache.get_by_slot_full(Slot::Ammo, breakpoint, AttackType::Slash, style);
-		let head = gear_cache.get_by_slot_full(Slot::Head, breakpoint, AttackType::Slash, style);
-		let cape = gear_cache.get_by_slot_full(Slot::Cape, breakpoint, AttackType::Slash, style);
-		let neck = gear_cache.get_by_slot_full(Slot::Neck, breakpoint, AttackType::Slash, style);
-		let body = gear_cache.get_by_slot_full(Slot::Body, breakpoint, AttackType::Slash, style);
-		let legs = gear_cache.get_by_slot_full(Slot::Legs, breakpoint, AttackType::Slash, style);
-		let shield = gear_cache.get_by_slot_full(Slot::Shield, breakpoint, AttackType::Slash, style);
-		let hands = gear_cache.get_by_slot_full(Slot::Hands, breakpoint, AttackType::Slash, style);
-		let feet = gear_cache.get_by_slot_full(Slot::Feet, breakpoint, AttackType::Slash, style);
-		let ring = gear_cache.get_by_slot_full(Slot::Ring, breakpoint, AttackType::Slash, style);
-
-		let all = vec![
-			filter_elided_items(&weapon),
-			filter_elided_items(&ammo),
-			filter_elided_items(&head),
-			filter_elided_items(&cape),
-			filter_elided_items(&neck),
-			filter_elided_items(&body),
-			filter_elided_items(&legs),
-			filter_elided_items(&shield),
-			filter_elided_items(&hands),
-			filter_elided_items(&feet),
-			filter_elided_items(&ring),
-		];
+	pub fn xp_per_hour(&self, style: AttackStyle, items: &[ItemGroup], target: &Target, kill_model: &KillModel) -> f64 {
+		target.xp_per_kill() * self.kill_stats(style, items, target, kill_model).kills_per_hour
+	}
+
+	// hours to gain `xp`, shared between every skill `style` trains
+	fn hours_to_gain(&self, style: AttackStyle, xp: u64, items: &[ItemGroup], spot: &TrainingSpot) -> f64 {
+		let kills = self.kill_stats(style, items, &spot.target, &spot.kill_model);
+		if let Some(damage) = &kills.damage {
+			if !spot.kill_model.upkeep.allows(damage) {
+				return f64::INFINITY;
+			}
+		}
+		(xp as f64) / (spot.target.xp_per_kill() * kills.kills_per_hour)
+	}
+
+	// the attack, strength or defence level a style trains
+	fn trained_level(&mut self, skill: Skill) -> &mut Level {
+		match skill {
+			Skill::Attack   => &mut self.attack,
+			Skill::Strength => &mut self.strength,
+			Skill::Defence  => &mut self.defence,
+			_               => unreachable!("only melee skills are trained"),
+		}
+	}
+
+	pub fn successors(&self, planner: &Planner) -> Vec<(Self, NotNan<f64>)> {
+		let mut v = Vec::with_capacity(4);
+		if let Some(successor) = self.successor(AttackStyle::Accurate,   planner) { v.push(successor) }
+		if let Some(successor) = self.successor(AttackStyle::Aggressive, planner) { v.push(successor) }
+		if let Some(successor) = self.successor(AttackStyle::Defensive,  planner) { v.push(successor) }
+		if let Some(successor) = self.successor(AttackStyle::Controlled, planner) { v.push(successor) }
+		v
+	}
+
+	fn successor(&self, style: AttackStyle, planner: &Planner) -> Option<(Self, NotNan<f64>)> {
+		let gear_cache = planner.gear_cache;
+		let goal = &planner.goal;
+		let breakpoint = gear_cache.get_breakpoint(self.levels());
+
+		// the xp goes to every trained skill in equal shares until the first of
+		// them levels, and none of them may go past the goal. the skills that
+		// didn't level drop their share, it's still paid for but states stay
+		// whole levels and don't multiply by every xp they could be at
+		let skills = XpSplit::melee(style).skills();
+		if skills.iter().any(|skill| self.level(*skill) + 1 > 127 || self.level(*skill) + 1 > goal.level(*skill)) {
+			return None;
+		}
+		let mut next = self.clone();
+		let share = skills.iter().map(|skill| next.trained_level(*skill).xp_to_next_level()).min()?;
+		for skill in skills {
+			let trained = next.trained_level(*skill);
+			*trained = Level::new(xp_to_level(trained.xp + share));
+		}
+		let gained_xp = share * skills.len() as u64;
+		// hitpoints gets a third of the xp of whatever is being trained
+		next.hitpoints = Level::from_xp(next.hitpoints.xp + gained_xp / 3);
+
+		// weapons are looked up by the stance being trained, so checking the
+		// style here also keeps out weapons that could only train a capped skill
+		if !planner.constraints.allows(self, &next) {
+			return None;
+		}
 
-		let mut max_hours = std::f64::INFINITY;
-		let mut gear = None;
-		for set in all.into_iter().multi_cartesian_product() {
-			let hours = self.hours_to_level(style, &set);
-			if hours < max_hours {
-				max_hours = hours;
-				gear.replace(set);
+		// every spot is searched with its own target, and moving to a different
+		// spot than the last level was trained at costs the travel there
+		let mut max_hours = f64::INFINITY;
+		let mut best = None;
+		for (index, spot) in planner.spots.iter().enumerate() {
+			let travel_hours = if self.spot_that_got_us_here.map_or(index == 0, |previous| previous == index) {
+				0.0
+			} else {
+				spot.travel_seconds / (60.0 * 60.0)
+			};
+
+			// every attack type is searched on its own, armour counts its bonus for
+			// the weapon it's worn with
+			let searches: Vec<Vec<Vec<ItemGroup>>> = MELEE_ATTACK_TYPES.iter()
+				.flat_map(|attack_type| self.searches(style, *attack_type, breakpoint, planner, spot))
+				.collect();
+
+			for set in searches.into_iter().flat_map(|all| all.into_iter().multi_cartesian_product()) {
+				let hours = self.hours_to_gain(style, gained_xp, &set, spot) + travel_hours;
+				if hours < max_hours {
+					max_hours = hours;
+					best.replace((index, set));
+				}
 			}
 		}
 
-		if let None = gear {
-			unreachable!("couldn't find gear");
+		// no gear when there's no weapon to train with, or every set takes more
+		// damage than the upkeep allows
+		let (index, gear) = best?;
+		let spot = &planner.spots[index];
+
+		next.kills_that_got_us_here = Some(self.kill_stats(style, &gear, &spot.target, &spot.kill_model));
+		next.gear_that_got_us_here = Some(gear);
+		next.spot_that_got_us_here = Some(index);
+		next.style_that_got_us_here = Some(style);
+		Some((next, NotNan::new(max_hours).ok()?))
+	}
+
+	// the candidates of every slot for one attack type, once as they are and
+	// once for every full set. nothing to search without a weapon of that type
+	fn searches(&self, style: AttackStyle, attack_type: AttackType, breakpoint: Breakpoint, planner: &Planner, spot: &TrainingSpot) -> Vec<Vec<Vec<ItemGroup>>> {
+		let gear_cache = planner.gear_cache;
+		let defend_against = spot.target.attack.map(|attack| attack.attack_type);
+		let specials = &self.boosts.specials;
+		let allowed: Vec<Vec<ItemGroup>> = SLOTS.iter()
+			.map(|slot| match slot {
+				// the lightbearer takes the ring slot
+				Slot::Ring if specials.lightbearer => Vec::new(),
+				_ => planner.rules.allowed(*slot, &gear_cache.get_by_slot_full(*slot, breakpoint, attack_type, style, defend_against)),
+			})
+			.collect();
+		if !allowed[0].iter().any(|group| group.attack_speed.is_some()) {
+			return Vec::new();
 		}
 
-		Some((Self::new(attack, strength, defence, gear), NotNan::new(max_hours).unwrap()))
+		// passives that work against the target and weapons with a special
+		// attack to use are better than their stats say, so they're kept
+		// whether or not they were elided
+		let keep = |group: &ItemGroup| {
+			group.passive.is_some_and(|passive| passive.applies(&spot.target)) ||
+			(specials.enabled && specials.swap.is_none() && group.special.is_some())
+		};
+		let all: Vec<Vec<ItemGroup>> = allowed.iter()
+			.map(|groups| {
+				let mut candidates = planner.rules.candidates(groups, attack_type, &planner.objectives);
+				candidates.extend(groups.iter()
+					.filter(|group| keep(group))
+					.filter(|group| !candidates.iter().any(|candidate| candidate.item_ids == group.item_ids))
+					.cloned()
+					.collect::<Vec<_>>());
+				candidates
+			})
+			.collect();
+
+		// set pieces tend to be elided on their own stats, so every full set
+		// that can be worn gets a search of its own
+		let mut searches = vec![all.clone()];
+		searches.extend(FULL_SETS.iter().filter_map(|(_, set)| set_search(set, &SLOTS, &allowed, &all)));
+		searches.into_iter()
+			.flat_map(|search| split_two_handed(search, attack_type))
+			.collect()
+	}
+}
+
+// one-handed weapons are searched with the shields, two-handed weapons with
+// nothing in the shield slot
+fn split_two_handed(search: Vec<Vec<ItemGroup>>, attack_type: AttackType) -> Vec<Vec<Vec<ItemGroup>>> {
+	let shield = SLOTS.iter().position(|slot| *slot == Slot::Shield).unwrap();
+	let (two_handed, one_handed): (Vec<ItemGroup>, Vec<ItemGroup>) = search[0].iter().cloned().partition(|group| group.two_handed);
+	let mut searches = Vec::with_capacity(2);
+	if !two_handed.is_empty() {
+		let mut two_handed_search = search.clone();
+		two_handed_search[0] = two_handed;
+		two_handed_search[shield] = vec![ItemGroup::empty_group(attack_type)];
+		searches.push(two_handed_search);
 	}
+	if !one_handed.is_empty() {
+		let mut one_handed_search = search;
+		one_handed_search[0] = one_handed;
+		searches.push(one_handed_search);
+	}
+	searches
 }
 
-fn sum_stats(items: &Vec<ItemGroup>) -> (RunescapeInt, RunescapeInt) {
+fn sum_stats(items: &[ItemGroup]) -> (RunescapeInt, RunescapeInt) {
 	let mut attack_bonus = 0;
 	let mut strength_bonus = 0;
 	for item in items {
@@ -208,7 +652,21 @@ fn sum_stats(items: &Vec<ItemGroup>) -> (RunescapeInt, RunescapeInt) {
 	(attack_bonus, strength_bonus)
 }
 
-fn find_weapon_speed(items: &Vec<ItemGroup>) -> Option<RunescapeInt> {
+// the invisible levels of the weapon's stance, a punch in `style` without one
+pub fn stance_bonus(style: AttackStyle, items: &[ItemGroup]) -> StyleBonus {
+	items.iter()
+		.find(|item| item.attack_speed.is_some())
+		.map_or(StyleBonus::melee(style), |item| item.style_bonus)
+}
+
+fn find_attack_type(items: &[ItemGroup]) -> AttackType {
+	items.iter()
+		.find(|item| item.attack_speed.is_some())
+		.or_else(|| items.first())
+		.map_or(AttackType::Slash, |item| item.attack_type)
+}
+
+fn find_weapon_speed(items: &[ItemGroup]) -> Option<RunescapeInt> {
 	for item in items {
 		if let Some(speed) = item.attack_speed {
 			return Some(speed);
@@ -217,11 +675,14 @@ fn find_weapon_speed(items: &Vec<ItemGroup>) -> Option<RunescapeInt> {
 	None
 }
 
+// the spot is part of the state, the next level costs travel from anywhere
+// else. the start is at the first spot
 impl PartialEq for Melee {
 	fn eq(&self, other: &Self) -> bool {
 		self.attack == other.attack &&
 		self.strength == other.strength &&
-		self.defence == other.defence
+		self.defence == other.defence &&
+		self.spot_that_got_us_here.unwrap_or(0) == other.spot_that_got_us_here.unwrap_or(0)
 	}
 }
 
@@ -230,6 +691,7 @@ impl Hash for Melee {
         self.attack.hash(state);
         self.strength.hash(state);
         self.defence.hash(state);
+        self.spot_that_got_us_here.unwrap_or(0).hash(state);
     }
 }
 
@@ -240,3 +702,251 @@ impl std::fmt::Display for Melee {
 }
 
 impl Eq for Melee {}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::runescape::gear::{GearCache, WorldType, UNARMED_ID};
+	use crate::runescape::gear::passives::Passive;
+	use crate::runescape::gear::sets::{worn_sets, SetPiece};
+	use crate::runescape::gear::tests::{item, weapon};
+	use crate::runescape::graph::upkeep::Upkeep;
+
+	fn planner(gear_cache: &GearCache) -> Planner<'_> {
+		Planner {
+			gear_cache,
+			goal: Melee::new(99, 99, 99, None),
+			constraints: Default::default(),
+			spots: vec![TrainingSpot::new(Target::sand_crab(), KillModel::default())],
+			rules: Default::default(),
+			objectives: Vec::new(),
+		}
+	}
+
+	#[test]
+	fn two_handed_weapons_leave_the_shield_slot_empty() {
+		let weapon = |id, two_handed| ItemGroup { item_ids: vec![id], attack_speed: Some(4), two_handed, ..ItemGroup::empty_group(AttackType::Slash) };
+		let mut search: Vec<Vec<ItemGroup>> = SLOTS.iter().map(|_| vec![ItemGroup { item_ids: vec![0], ..ItemGroup::empty_group(AttackType::Slash) }]).collect();
+		search[0] = vec![weapon(1, false), weapon(2, true)];
+		let shield = SLOTS.iter().position(|slot| *slot == Slot::Shield).unwrap();
+
+		let searches = split_two_handed(search, AttackType::Slash);
+		assert_eq!(searches.len(), 2);
+		let (two_handed, one_handed) = (&searches[0], &searches[1]);
+		assert_eq!(two_handed[0].iter().map(|group| group.item_ids[0]).collect::<Vec<_>>(), vec![2]);
+		assert!(two_handed[shield].iter().all(|group| group.item_ids.is_empty()));
+		assert_eq!(one_handed[0].iter().map(|group| group.item_ids[0]).collect::<Vec<_>>(), vec![1]);
+		assert_eq!(one_handed[shield][0].item_ids, vec![0]);
+	}
+
+	#[test]
+	fn reductions_are_thrown_on_the_kills_energy_pays_for() {
+		let player = Melee::new(99, 99, 99, None);
+		let target = |hitpoints| Target {
+			hitpoints,
+			defence_level: 200,
+			defence_reductions: vec![DefenceReduction::DragonWarhammer],
+			..Target::sand_crab()
+		};
+		let energy_per_hour = player.boosts().specials.energy_per_second() * 60.0 * 60.0;
+
+		// quick kills outpace regeneration, every bit of it goes to the reductions
+		let quick = player.kill_stats(AttackStyle::Aggressive, &[], &target(10), &KillModel::default());
+		let share = quick.reduced_share.unwrap();
+		assert!(share > 0.0 && share < 1.0, "{}", share);
+		let spent = share * quick.kills_per_hour * SpecialAttack::DragonWarhammer.energy();
+		assert!((spent - energy_per_hour).abs() < 1e-6, "{} {}", spent, energy_per_hour);
+
+		// slow kills regenerate more than the reductions need
+		let slow = player.kill_stats(AttackStyle::Aggressive, &[], &target(2000), &KillModel::default());
+		assert_eq!(slow.reduced_share, Some(1.0));
+		assert!(slow.kills_per_hour * SpecialAttack::DragonWarhammer.energy() <= energy_per_hour);
+	}
+
+	#[test]
+	fn controlled_shares_the_xp_until_the_first_level() {
+		let spear = weapon(1237, "Bronze spear", "weapon", serde_json::json!({"attack_stab": 4, "attack_slash": 4, "attack_crush": 4, "melee_strength": 5}), "spear");
+		let gear_cache = GearCache::from_gear(std::iter::once((spear.id, spear)).collect(), |_| true, WorldType::Members);
+		let planner = planner(&gear_cache);
+		let start = Melee::new(50, 52, 55, None);
+
+		let (next, _) = start.successors(&planner).into_iter()
+			.find(|(next, _)| next.style_that_got_us_here == Some(AttackStyle::Controlled))
+			.unwrap();
+		assert!(next.gear_that_got_us_here.as_ref().unwrap()[0].item_ids.contains(&1237));
+		let share = [start.attack.xp_to_next_level(), start.strength.xp_to_next_level(), start.defence.xp_to_next_level()].iter().copied().min().unwrap();
+		assert_eq!(next.xp(Skill::Attack) - start.xp(Skill::Attack), share);
+		// the skills that didn't level drop their share, hitpoints still gets all of it
+		assert_eq!(next.xp(Skill::Strength), start.xp(Skill::Strength));
+		assert_eq!(next.xp(Skill::Defence), start.xp(Skill::Defence));
+		assert_eq!(next.hitpoints.xp - start.hitpoints.xp, share);
+		assert_eq!((next.attack.value, next.strength.value, next.defence.value), (51, 52, 55));
+	}
+
+	#[test]
+	fn controlled_gives_a_level_to_each_and_splits_the_xp() {
+		let player = Melee::new(60, 60, 60, None);
+		let controlled = StyleBonus::melee(AttackStyle::Controlled);
+		let none = StyleBonus::default();
+		let gear = GearBonus::default();
+		assert_eq!(player.effective_attack(controlled, &gear), player.effective_attack(none, &gear) + 1);
+		assert_eq!(player.effective_strength(controlled, &gear), player.effective_strength(none, &gear) + 1);
+		assert_eq!(player.effective_defence(controlled), player.effective_defence(none) + 1);
+
+		// level with level, a third of the xp goes to each and all of them level together
+		let spear = weapon(1237, "Bronze spear", "weapon", serde_json::json!({"attack_stab": 4, "attack_slash": 4, "attack_crush": 4, "melee_strength": 5}), "spear");
+		let gear_cache = GearCache::from_gear(std::iter::once((spear.id, spear)).collect(), |_| true, WorldType::Members);
+		let planner = planner(&gear_cache);
+		let (next, _) = player.successor(AttackStyle::Controlled, &planner).unwrap();
+		let share = level_to_xp(61) - level_to_xp(60);
+		for skill in &[Skill::Attack, Skill::Strength, Skill::Defence] {
+			assert_eq!(next.xp(*skill) - player.xp(*skill), share);
+			assert_eq!(next.level(*skill), 61);
+		}
+		assert_eq!(next.hitpoints.xp - player.hitpoints.xp, share);
+	}
+
+	#[test]
+	fn controlled_paths_to_the_same_levels_are_the_same_state() {
+		let spear = weapon(1237, "Bronze spear", "weapon", serde_json::json!({"attack_stab": 4, "attack_slash": 4, "attack_crush": 4, "melee_strength": 5}), "spear");
+		let gear_cache = GearCache::from_gear(std::iter::once((spear.id, spear)).collect(), |_| true, WorldType::Members);
+		let planner = planner(&gear_cache);
+		let start = Melee::new(50, 50, 50, None);
+		let step = |from: &Melee, style| from.successor(style, &planner).unwrap().0;
+
+		// all three level at once, or defence first and a controlled step that
+		// levels attack and strength while defence is a level further along
+		let together = step(&start, AttackStyle::Controlled);
+		let defence_first = step(&step(&start, AttackStyle::Defensive), AttackStyle::Controlled);
+		assert!(together.same_levels(&Melee::new(51, 51, 51, None)), "{}", together);
+		assert!(defence_first.same_levels(&Melee::new(51, 51, 51, None)), "{}", defence_first);
+		for skill in &[Skill::Attack, Skill::Strength, Skill::Defence] {
+			assert_eq!(together.xp(*skill), level_to_xp(51));
+			assert_eq!(defence_first.xp(*skill), level_to_xp(51));
+		}
+		assert_eq!(together, defence_first);
+
+		// xp left over from the start keeps states apart
+		let mut partial = Melee::new(51, 51, 51, None);
+		partial.defence = Level::from_xp(level_to_xp(51) + 100);
+		assert!(partial.same_levels(&together));
+		assert_ne!(partial, together);
+	}
+
+	#[test]
+	fn states_at_different_spots_are_different() {
+		let mut here = Melee::new(50, 50, 50, None);
+		let mut there = Melee::new(50, 50, 50, None);
+		assert_eq!(here, there);
+		there.spot_that_got_us_here = Some(1);
+		assert_ne!(here, there);
+		assert!(here.same_levels(&there));
+		here.spot_that_got_us_here = Some(0);
+		assert_eq!(here, Melee::new(50, 50, 50, None));
+	}
+
+	#[test]
+	fn full_void_beats_better_pieces_on_their_own() {
+		let gear = vec![
+			weapon(1333, "Rune scimitar", "weapon", serde_json::json!({"attack_slash": 45, "melee_strength": 44}), "slash_sword"),
+			item(11665, "Void melee helm", "head", serde_json::json!({})),
+			item(8839, "Void knight top", "body", serde_json::json!({})),
+			item(8840, "Void knight robe", "legs", serde_json::json!({})),
+			item(8842, "Void knight gloves", "hands", serde_json::json!({})),
+			// each a little better than its void piece
+			item(1, "Strong helm", "head", serde_json::json!({"attack_slash": 1, "melee_strength": 1})),
+			item(2, "Strong top", "body", serde_json::json!({"attack_slash": 1, "melee_strength": 1})),
+			item(3, "Strong legs", "legs", serde_json::json!({"attack_slash": 1, "melee_strength": 1})),
+			item(4, "Strong gloves", "hands", serde_json::json!({"attack_slash": 1, "melee_strength": 1})),
+		];
+		let gear_cache = GearCache::from_gear(gear.into_iter().map(|item| (item.id, item)).collect(), |_| true, WorldType::Members);
+		let planner = planner(&gear_cache);
+		let start = Melee::new(70, 70, 70, None);
+
+		let (next, _) = start.successors(&planner).into_iter()
+			.find(|(next, _)| next.style_that_got_us_here == Some(AttackStyle::Aggressive))
+			.unwrap();
+		let worn = next.gear_that_got_us_here.unwrap();
+		assert_eq!(worn_sets(&worn), vec!["void"]);
+
+		// a tenth more effective strength is worth more than 4 strength bonus
+		let void = start.gear_bonus(&worn, &Target::sand_crab());
+		assert_eq!(void.effective_level, 1.1);
+		let aggressive = StyleBonus::melee(AttackStyle::Aggressive);
+		assert_eq!(start.effective_strength(aggressive, &void), 89);
+		assert_eq!(start.max_hit(aggressive, 44, &void), 15);
+		assert_eq!(start.max_hit(aggressive, 48, &GearBonus::default()), 14);
+	}
+
+	#[test]
+	fn incoming_dps_rolls_the_target_against_our_defence() {
+		let attack = MonsterAttack { max_hit: 10, attack_speed: 4, ..MonsterAttack::default() };
+		let player = Melee::new(1, 1, 1, None);
+		// a 640 attack roll against (1 + 8) * 64 = 576, every hit uniform up to 10
+		let hit_chance = 1.0 - 578.0 / (2.0 * 641.0);
+		let expected = hit_chance * 5.0 / 2.4;
+		let dps = player.incoming_dps(StyleBonus::default(), 0, &attack, &GearBonus::default());
+		assert!((dps - expected).abs() < 1e-9, "{} {}", dps, expected);
+		let justiciar = GearBonus { damage_taken: 0.9, ..GearBonus::default() };
+		assert!((player.incoming_dps(StyleBonus::default(), 0, &attack, &justiciar) - expected * 0.9).abs() < 1e-9);
+	}
+
+	#[test]
+	fn gear_taking_too_much_damage_is_not_worn() {
+		let gear = vec![weapon(1333, "Rune scimitar", "weapon", serde_json::json!({"attack_slash": 45, "melee_strength": 44}), "slash_sword")];
+		let gear_cache = GearCache::from_gear(gear.into_iter().map(|item| (item.id, item)).collect(), |_| true, WorldType::Members);
+		let target = Target {
+			hitpoints: 100,
+			attack: Some(MonsterAttack { max_hit: 20, attack_level: 80, attack_type: AttackType::Slash, ..MonsterAttack::default() }),
+			..Target::sand_crab()
+		};
+		let spot = |max_damage_per_hour| {
+			let upkeep = Upkeep { max_damage_per_hour, ..Upkeep::default() };
+			TrainingSpot::new(target.clone(), KillModel { upkeep, ..KillModel::default() })
+		};
+		let start = Melee::new(60, 60, 60, None);
+
+		let unlimited = Planner { spots: vec![spot(None)], ..planner(&gear_cache) };
+		assert!(!start.successors(&unlimited).is_empty());
+		let limited = Planner { spots: vec![spot(Some(10.0))], ..planner(&gear_cache) };
+		assert!(start.successors(&limited).is_empty());
+	}
+
+	#[test]
+	fn dps_counts_passives_and_sets() {
+		let player = Melee::new(80, 80, 80, None);
+		let arclight = ItemGroup {
+			item_ids: vec![19675],
+			attack_value: 70,
+			strength_value: 72,
+			attack_speed: Some(4),
+			passive: Some(Passive::Arclight),
+			..ItemGroup::empty_group(AttackType::Slash)
+		};
+		let target = Target { hitpoints: 200, defence_level: 100, defence_slash: 50, ..Target::sand_crab() };
+		let demon = Target { attributes: std::iter::once(Attribute::Demon).collect(), ..target.clone() };
+		let kill_model = KillModel::default();
+		let plain = player.dps(AttackStyle::Aggressive, std::slice::from_ref(&arclight), &target, &kill_model);
+		let against_demons = player.dps(AttackStyle::Aggressive, std::slice::from_ref(&arclight), &demon, &kill_model);
+		assert!(against_demons > plain * 1.7, "{} {}", against_demons, plain);
+
+		let berserker = ItemGroup { item_ids: vec![11128], set_piece: Some(SetPiece::BerserkerNecklace), ..ItemGroup::empty_group(AttackType::Slash) };
+		let obsidian = ItemGroup { item_ids: vec![6523], set_piece: Some(SetPiece::ObsidianWeapon), passive: None, ..arclight };
+		let without = player.dps(AttackStyle::Aggressive, std::slice::from_ref(&obsidian), &target, &kill_model);
+		let with = player.dps(AttackStyle::Aggressive, &[obsidian, berserker], &target, &kill_model);
+		assert!(with > without * 1.15, "{} {}", with, without);
+	}
+
+	#[test]
+	fn level_one_can_always_punch() {
+		let gear = vec![weapon(4587, "Dragon scimitar", "weapon", serde_json::json!({"attack_slash": 67, "requirements": {"attack": 60}}), "slash_sword")];
+		let gear_cache = GearCache::from_gear(gear.into_iter().map(|item| (item.id, item)).collect(), |_| true, WorldType::Members);
+		let planner = planner(&gear_cache);
+		let successors = Melee::new(1, 1, 1, None).successors(&planner);
+		assert_eq!(successors.len(), 3);
+		for (next, _) in successors {
+			let worn = next.gear_that_got_us_here.unwrap();
+			assert_eq!(worn[0].item_ids, vec![UNARMED_ID]);
+		}
+	}
+}
@@ -1,6 +1,15 @@
 use crate::runescape::RunescapeInt;
 
+pub mod boosts;
+pub mod constraints;
+pub mod damage;
+pub mod dps;
+pub mod kill;
 pub mod level;
+pub mod planner;
+pub mod simulation;
+pub mod spots;
+pub mod upkeep;
 
 const XP_TABLE: [u64; 127] = [
 	0,
@@ -142,5 +151,26 @@ pub fn xp_to_level(xp: u64) -> RunescapeInt {
 			return (XP_TABLE.len() - i) as RunescapeInt;
 		}
 	}
-	return XP_TABLE.len() as RunescapeInt;
+	XP_TABLE.len() as RunescapeInt
+}
+
+pub fn combat_level(attack: RunescapeInt, strength: RunescapeInt, defence: RunescapeInt, hitpoints: RunescapeInt, prayer: RunescapeInt, ranged: RunescapeInt, magic: RunescapeInt) -> RunescapeInt {
+	let base = 0.25 * (defence + hitpoints + prayer / 2) as f64;
+	let melee = 0.325 * (attack + strength) as f64;
+	let range = 0.325 * (ranged * 3 / 2) as f64;
+	let mage = 0.325 * (magic * 3 / 2) as f64;
+	(base + melee.max(range).max(mage)) as RunescapeInt
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn combat_level_of_new_and_maxed_accounts() {
+		assert_eq!(combat_level(1, 1, 1, 10, 1, 1, 1), 3);
+		assert_eq!(combat_level(99, 99, 99, 99, 99, 99, 99), 126);
+		// ranged and magic count a level and a half, melee doesn't add to them
+		assert_eq!(combat_level(1, 1, 1, 10, 1, 99, 1), 50);
+	}
 }
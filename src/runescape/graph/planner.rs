@@ -0,0 +1,35 @@
+use crate::runescape::gear::GearCache;
+use crate::runescape::gear::item_group::Objective;
+use crate::runescape::gear::rules::GearRules;
+use crate::runescape::graph::constraints::BuildConstraints;
+use crate::runescape::graph::level::Melee;
+use crate::runescape::graph::spots::TrainingSpot;
+
+// everything a plan is searched against, besides the state being expanded
+pub struct Planner<'a> {
+	pub gear_cache:  &'a GearCache,
+	pub goal:        Melee,
+	pub constraints: BuildConstraints,
+	// every level is trained at whichever of these is quickest, the first
+	// is where the plan starts
+	pub spots:       Vec<TrainingSpot>,
+	pub rules:       GearRules,
+	// what gear is pruned on before sets are compared
+	pub objectives:  Vec<Objective>,
+}
+
+impl<'a> Planner<'a> {
+	// the spot a step of the plan was trained at
+	pub fn spot(&self, step: &Melee) -> &TrainingSpot {
+		&self.spots[step.spot_that_got_us_here.unwrap_or(0)]
+	}
+
+	// hours spent getting to a step's spot from the one before it
+	pub fn travel_hours(&self, previous: &Melee, step: &Melee) -> f64 {
+		if previous.spot_that_got_us_here.unwrap_or(0) == step.spot_that_got_us_here.unwrap_or(0) {
+			0.0
+		} else {
+			self.spot(step).travel_seconds / (60.0 * 60.0)
+		}
+	}
+}
@@ -0,0 +1,324 @@
+use std::collections::BTreeMap;
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use crate::runescape::RunescapeInt;
+use crate::runescape::gear::special::{ReducedDefence, FULL_BAR};
+use crate::runescape::graph::level::{Melee, GAME_TICK};
+use crate::runescape::graph::planner::Planner;
+use crate::runescape::hiscores::Skill;
+use crate::runescape::monster::{Attribute, Target};
+use crate::runescape::osrsbox_db::types::XpSplit;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Percentiles {
+	pub p10: f64,
+	pub p50: f64,
+	pub p90: f64,
+	pub mean: f64,
+}
+
+impl Percentiles {
+	fn from_samples(mut samples: Vec<f64>) -> Self {
+		samples.sort_by(|a, b| a.partial_cmp(b).unwrap());
+		let at = |fraction: f64| samples[((samples.len() - 1) as f64 * fraction).round() as usize];
+		Self {
+			p10: at(0.1),
+			p50: at(0.5),
+			p90: at(0.9),
+			mean: samples.iter().sum::<f64>() / samples.len() as f64,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SegmentReport {
+	// hours, as simulated
+	pub simulated: Percentiles,
+	// hours, from `Melee::xp_per_hour`
+	pub analytic: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulationReport {
+	pub segments: Vec<SegmentReport>,
+	pub total: Percentiles,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TargetState {
+	// hitpoints and defence left, and whether its reductions were tried yet
+	Alive { hitpoints: RunescapeInt, defence: RunescapeInt, reduced: bool },
+	RespawnsAt(u64),
+}
+
+impl TargetState {
+	fn spawned(target: &Target) -> Self {
+		TargetState::Alive { hitpoints: target.hitpoints, defence: target.defence_level, reduced: false }
+	}
+}
+
+// one playthrough of the whole plan, returning the hours spent on each segment.
+// leftover xp carries over from one segment to the next, and so do the
+// targets while the plan stays at the same spot
+fn simulate_once(plan: &[Melee], planner: &Planner, rng: &mut StdRng) -> Vec<f64> {
+	let mut targets = Vec::new();
+	let mut current = 0;
+	let mut tick = 0;
+	let mut xp = [Skill::Attack, Skill::Strength, Skill::Defence].iter()
+		.map(|skill| (*skill, plan[0].xp(*skill) as f64))
+		.collect::<BTreeMap<_, _>>();
+
+	// the special bar starts full and keeps regenerating between segments
+	let mut energy = FULL_BAR;
+	let mut energy_tick = 0;
+
+	let mut segment_hours = Vec::with_capacity(plan.len() - 1);
+	for step in plan.windows(2) {
+		let style = step[1].style_that_got_us_here.expect("plan step without a style");
+		let skills = XpSplit::melee(style).skills();
+		// every point of damage is worth 4 xp, shared between the trained skills
+		let xp_per_damage = 4.0 / skills.len() as f64;
+		let gain = |xp: &mut BTreeMap<Skill, f64>, damage: RunescapeInt| {
+			for skill in skills {
+				*xp.get_mut(skill).unwrap() += damage as f64 * xp_per_damage;
+			}
+		};
+		let gear = step[1].gear_that_got_us_here.as_ref().expect("plan step without gear");
+		let spot = planner.spot(&step[1]);
+		let target = &spot.target;
+		let respawn_ticks = (spot.kill_model.respawn_seconds / GAME_TICK).round() as u64;
+		let travel_hours = planner.travel_hours(&step[0], &step[1]);
+		if targets.is_empty() || travel_hours > 0.0 {
+			targets = vec![TargetState::spawned(target); spot.kill_model.targets.max(1) as usize];
+			current = 0;
+		}
+		// the hits against every defence level the reductions leave a target at
+		let mut attacks = BTreeMap::new();
+		let unreduced = ReducedDefence::none(target.defence_level);
+		let (hit, attack_speed) = step[0].attack(style, gear, target, &unreduced);
+		attacks.insert(target.defence_level, (hit.clone(), step[0].special_attack(style, gear, target, &unreduced)));
+		let energy_per_tick = step[0].boosts().specials.energy_per_second() * GAME_TICK;
+		// the weapon's special leaves enough energy for the next kill's reductions
+		let reduction_energy: f64 = target.defence_reductions.iter().map(|reduction| reduction.special().energy()).sum();
+		let demon = target.is(Attribute::Demon);
+		let start_tick = tick;
+
+		if hit.probability(0) >= 1.0 {
+			segment_hours.push(f64::INFINITY);
+			continue;
+		}
+
+		while skills.iter().any(|skill| xp[skill] < step[1].xp(*skill) as f64) {
+			for state in targets.iter_mut() {
+				if let TargetState::RespawnsAt(at) = *state {
+					if at <= tick {
+						*state = TargetState::spawned(target);
+					}
+				}
+			}
+
+			// stay on the current target, otherwise move to anything alive or
+			// wait for the next respawn
+			if let TargetState::RespawnsAt(_) = targets[current] {
+				match targets.iter().position(|state| matches!(state, TargetState::Alive { .. })) {
+					Some(alive) => current = alive,
+					None => {
+						tick = targets.iter().filter_map(|state| match state {
+							TargetState::RespawnsAt(at) => Some(*at),
+							TargetState::Alive { .. }   => None,
+						}).min().unwrap();
+						continue;
+					},
+				}
+			}
+
+			energy = (energy + energy_per_tick * (tick - energy_tick) as f64).min(FULL_BAR);
+			energy_tick = tick;
+			let (mut remaining, mut defence, reduced) = match targets[current] {
+				TargetState::Alive { hitpoints, defence, reduced } => (hitpoints, defence, reduced),
+				TargetState::RespawnsAt(_)                         => unreachable!(),
+			};
+
+			if !reduced {
+				// the reductions start the kill whenever the bar holds all of them
+				if !target.defence_reductions.is_empty() && energy >= reduction_energy {
+					energy -= reduction_energy;
+					for reduction in &target.defence_reductions {
+						let (hit_chance, max_hit) = step[0].reduction_roll(style, gear, target, *reduction, defence);
+						if rng.gen::<f64>() < hit_chance {
+							let damage = rng.gen_range(0, max_hit.max(0) + 1);
+							defence = reduction.reduce(defence, target.defence_level, damage, demon);
+							let damage = damage.min(remaining);
+							gain(&mut xp, damage);
+							remaining -= damage;
+						}
+						tick += reduction.attack_speed() as u64;
+					}
+				}
+				targets[current] = if remaining == 0 {
+					TargetState::RespawnsAt(tick + respawn_ticks)
+				} else {
+					TargetState::Alive { hitpoints: remaining, defence, reduced: true }
+				};
+				continue;
+			}
+
+			let (hit, special) = attacks.entry(defence).or_insert_with(|| {
+				let reduced = ReducedDefence::none(defence);
+				(step[0].attack(style, gear, target, &reduced).0, step[0].special_attack(style, gear, target, &reduced))
+			});
+			let (attack, attack_ticks) = match special {
+				Some((special, special_hit, special_speed)) if energy - reduction_energy >= special.energy() => {
+					energy -= special.energy();
+					(&*special_hit, if special.instant() { 0 } else { *special_speed })
+				},
+				_ => (&*hit, attack_speed),
+			};
+
+			let damage = attack.inverse_cdf(rng.gen::<f64>()).min(remaining);
+			gain(&mut xp, damage);
+			targets[current] = if damage == remaining {
+				TargetState::RespawnsAt(tick + respawn_ticks)
+			} else {
+				TargetState::Alive { hitpoints: remaining - damage, defence, reduced: true }
+			};
+			tick += attack_ticks as u64;
+		}
+
+		// eating, banking and resetting aggression aren't simulated, they
+		// stretch the time spent attacking
+		let downtime = step[1].kills_that_got_us_here
+			.and_then(|kills| kills.damage)
+			.map_or(0.0, |damage| damage.downtime);
+		let attacking = ticks_to_hours(tick - start_tick) / (1.0 - downtime) / spot.kill_model.aggression_uptime();
+		segment_hours.push(travel_hours + attacking);
+	}
+	segment_hours
+}
+
+fn ticks_to_hours(ticks: u64) -> f64 {
+	ticks as f64 * GAME_TICK / (60.0 * 60.0)
+}
+
+pub fn simulate_plan(plan: &[Melee], planner: &Planner, runs: usize, seed: u64) -> SimulationReport {
+	let mut rng = StdRng::seed_from_u64(seed);
+	let segments = plan.len().saturating_sub(1);
+	let runs = runs.max(1);
+
+	let mut segment_samples = vec![Vec::with_capacity(runs); segments];
+	let mut total_samples = Vec::with_capacity(runs);
+	for _ in 0..runs {
+		let hours = if segments == 0 { Vec::new() } else { simulate_once(plan, planner, &mut rng) };
+		for (samples, hours) in segment_samples.iter_mut().zip(&hours) {
+			samples.push(*hours);
+		}
+		total_samples.push(hours.iter().sum());
+	}
+
+	let segments = plan.windows(2)
+		.zip(segment_samples)
+		.map(|(step, samples)| {
+			let style = step[1].style_that_got_us_here.unwrap();
+			let gear = step[1].gear_that_got_us_here.as_ref().unwrap();
+			// every skill got the share of the one that levelled, the others dropped theirs
+			let skills = XpSplit::melee(style).skills();
+			let share = skills.iter().map(|skill| step[1].xp(*skill).saturating_sub(step[0].xp(*skill))).max().unwrap_or(0);
+			let xp = (share * skills.len() as u64) as f64;
+			let spot = planner.spot(&step[1]);
+			SegmentReport {
+				simulated: Percentiles::from_samples(samples),
+				analytic: planner.travel_hours(&step[0], &step[1]) + xp / step[0].xp_per_hour(style, gear, &spot.target, &spot.kill_model),
+			}
+		})
+		.collect();
+
+	SimulationReport {
+		segments,
+		total: Percentiles::from_samples(total_samples),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::runescape::gear::{GearCache, WorldType};
+	use crate::runescape::gear::special::DefenceReduction;
+	use crate::runescape::graph::kill::KillModel;
+	use crate::runescape::graph::spots::TrainingSpot;
+	use crate::runescape::osrsbox_db::types::AttackStyle;
+
+	#[test]
+	fn simulated_reductions_match_the_analytic_rate() {
+		let gear_cache = GearCache::from_gear(BTreeMap::new(), |_| true, WorldType::Members);
+		let target = Target {
+			hitpoints: 20,
+			defence_level: 60,
+			defence_reductions: vec![DefenceReduction::DragonWarhammer],
+			..Target::sand_crab()
+		};
+		let mut trained = Melee::new(50, 51, 50, Some(Vec::new()));
+		trained.style_that_got_us_here = Some(AttackStyle::Aggressive);
+		let plan = [Melee::new(50, 50, 50, None), trained];
+		let planner = Planner {
+			gear_cache: &gear_cache,
+			goal: plan[1].clone(),
+			constraints: Default::default(),
+			spots: vec![TrainingSpot::new(target, KillModel::default())],
+			rules: Default::default(),
+			objectives: Vec::new(),
+		};
+
+		let report = simulate_plan(&plan, &planner, 20, 47);
+		let segment = report.segments[0];
+		let error = (segment.simulated.mean - segment.analytic).abs() / segment.analytic;
+		assert!(error < 0.05, "simulated {} hours, expected {}", segment.simulated.mean, segment.analytic);
+	}
+
+	// strength then attack from 50 to 51, punching
+	fn plan() -> Vec<Melee> {
+		let step = |attack, strength, style| {
+			let mut step = Melee::new(attack, strength, 50, Some(Vec::new()));
+			step.style_that_got_us_here = Some(style);
+			step
+		};
+		vec![Melee::new(50, 50, 50, None), step(50, 51, AttackStyle::Aggressive), step(51, 51, AttackStyle::Accurate)]
+	}
+
+	fn planner(gear_cache: &GearCache, target: Target) -> Planner<'_> {
+		Planner {
+			gear_cache,
+			goal: Melee::new(51, 51, 50, None),
+			constraints: Default::default(),
+			spots: vec![TrainingSpot::new(target, KillModel::default())],
+			rules: Default::default(),
+			objectives: Vec::new(),
+		}
+	}
+
+	#[test]
+	fn simulated_hours_match_the_analytic_hours() {
+		let gear_cache = GearCache::from_gear(BTreeMap::new(), |_| true, WorldType::Members);
+		let target = Target { hitpoints: 30, defence_level: 20, ..Target::sand_crab() };
+		let report = simulate_plan(&plan(), &planner(&gear_cache, target), 20, 7);
+		for segment in &report.segments {
+			let error = (segment.simulated.mean - segment.analytic).abs() / segment.analytic;
+			assert!(error < 0.05, "simulated {} hours, expected {}", segment.simulated.mean, segment.analytic);
+			assert!(segment.simulated.p10 <= segment.simulated.p50 && segment.simulated.p50 <= segment.simulated.p90);
+		}
+	}
+
+	#[test]
+	fn the_same_seed_gives_the_same_hours() {
+		let gear_cache = GearCache::from_gear(BTreeMap::new(), |_| true, WorldType::Members);
+		let planner = planner(&gear_cache, Target::sand_crab());
+		let hours = |seed| {
+			let report = simulate_plan(&plan(), &planner, 5, seed);
+			let total = report.total;
+			(total.p10, total.p50, total.p90, total.mean)
+		};
+		assert_eq!(hours(3), hours(3));
+		assert_ne!(hours(3), hours(4));
+	}
+}
@@ -0,0 +1,86 @@
+use std::io::Result as IoResult;
+
+use serde::Deserialize;
+
+use crate::runescape::{from_path, invalid_data, RunescapeInt};
+use crate::runescape::graph::kill::KillModel;
+use crate::runescape::graph::upkeep::Upkeep;
+use crate::runescape::monster::Target;
+
+// the spots that ship with the planner, see data/spots.toml
+const BUILTIN_SPOTS: &str = include_str!("../../../data/spots.toml");
+
+fn default_spawns() -> RunescapeInt {
+	1
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+struct SpotEntry {
+	name: String,
+	// anything --target takes
+	target: String,
+	#[serde(default = "default_spawns")]
+	spawns: RunescapeInt,
+	#[serde(default)]
+	respawn: f64,
+	#[serde(default)]
+	aggression_reset: f64,
+	#[serde(default)]
+	travel: f64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+struct SpotFile {
+	#[serde(default)]
+	spot: Vec<SpotEntry>,
+}
+
+// somewhere to train: what's fought there and how quickly it comes back
+#[derive(Debug, Clone)]
+pub struct TrainingSpot {
+	pub name: String,
+	pub target: Target,
+	pub kill_model: KillModel,
+	// seconds it takes to get there, paid every time the plan moves to it
+	pub travel_seconds: f64,
+}
+
+impl TrainingSpot {
+	// the one spot a plan without a list of spots trains at
+	pub fn new(target: Target, kill_model: KillModel) -> Self {
+		Self {
+			name: target.name.clone(),
+			target,
+			kill_model,
+			travel_seconds: 0.0,
+		}
+	}
+
+	fn from_file(file: SpotFile, upkeep: Upkeep) -> Result<Vec<Self>, String> {
+		file.spot.into_iter()
+			.map(|entry| {
+				let mut target: Target = entry.target.parse().map_err(|e| format!("spot {}: {}", entry.name, e))?;
+				target.name = entry.name.clone();
+				let kill_model = KillModel {
+					respawn_seconds: entry.respawn,
+					targets: entry.spawns,
+					aggression_reset_seconds: entry.aggression_reset,
+					upkeep,
+				};
+				Ok(Self { name: entry.name, target, kill_model, travel_seconds: entry.travel })
+			})
+			.collect()
+	}
+
+	// every spot shares the same food and banking
+	pub fn builtin(upkeep: Upkeep) -> Vec<Self> {
+		let file = toml::from_str(BUILTIN_SPOTS).expect("the builtin spots are valid");
+		Self::from_file(file, upkeep).expect("the builtin spots are valid")
+	}
+
+	pub fn from_path(path: &str, upkeep: Upkeep) -> IoResult<Vec<Self>> {
+		Self::from_file(from_path(path)?, upkeep).map_err(|e| invalid_data(format!("{}: {}", path, e)))
+	}
+}
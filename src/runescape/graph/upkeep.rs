@@ -0,0 +1,103 @@
+use crate::runescape::graph::level::GAME_TICK;
+
+// hitpoints come back at one every minute
+const REGENERATION_PER_HOUR: f64 = 60.0;
+// eating delays the next attack by three ticks
+const EAT_TICKS: f64 = 3.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Upkeep {
+	// hitpoints healed by one piece of food
+	pub food_heal: f64,
+	// pieces of food brought on each trip
+	pub food_per_trip: f64,
+	// seconds to bank and walk back once the food runs out
+	pub bank_seconds: f64,
+	// gear that takes more damage than this per hour isn't worn
+	pub max_damage_per_hour: Option<f64>,
+}
+
+impl Default for Upkeep {
+	// lobsters, a full inventory less the weapon and a teleport, and a couple
+	// of minutes to restock
+	fn default() -> Self {
+		Self {
+			food_heal: 12.0,
+			food_per_trip: 26.0,
+			bank_seconds: 120.0,
+			max_damage_per_hour: None,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DamageTaken {
+	pub damage_per_hour: f64,
+	pub food_per_hour: f64,
+	// the part of every hour spent eating or banking instead of attacking
+	pub downtime: f64,
+}
+
+impl Upkeep {
+	// `incoming_dps` is the target's damage per second while it's being fought,
+	// `combat_seconds` how much of an hour of attacking is spent fighting it.
+	// hitpoints come back between kills too, so all 60 count however short
+	// the fights are
+	pub fn damage_taken(&self, incoming_dps: f64, combat_seconds: f64) -> DamageTaken {
+		let damage = incoming_dps * combat_seconds;
+		let food = (damage - REGENERATION_PER_HOUR).max(0.0) / self.food_heal;
+		let eating = food * EAT_TICKS * GAME_TICK;
+		let banking = food / self.food_per_trip * self.bank_seconds;
+		let hour = 60.0 * 60.0;
+		let scale = hour / (hour + eating + banking);
+		DamageTaken {
+			damage_per_hour: damage * scale,
+			food_per_hour: food * scale,
+			downtime: 1.0 - scale,
+		}
+	}
+
+	pub fn allows(&self, damage: &DamageTaken) -> bool {
+		self.max_damage_per_hour.is_none_or(|max| damage.damage_per_hour <= max)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn close(a: f64, b: f64) -> bool {
+		(a - b).abs() < 1e-6
+	}
+
+	#[test]
+	fn food_and_banking_come_out_of_the_hour() {
+		// 360 damage less 60 regenerated is 25 lobsters, 45 seconds eating
+		// them and 25/26 of a 120 second bank trip
+		let damage = Upkeep::default().damage_taken(0.1, 3600.0);
+		let scale = 3600.0 / (3600.0 + 45.0 + 25.0 / 26.0 * 120.0);
+		assert!(close(damage.food_per_hour, 25.0 * scale), "{:?}", damage);
+		assert!(close(damage.damage_per_hour, 360.0 * scale), "{:?}", damage);
+		assert!(close(damage.downtime, 1.0 - scale), "{:?}", damage);
+	}
+
+	#[test]
+	fn regeneration_covers_the_whole_hour() {
+		// half an hour of fighting at 0.05 is 90 damage, regeneration heals 60 of it
+		let damage = Upkeep::default().damage_taken(0.05, 1800.0);
+		assert!(close(damage.food_per_hour * (1.0 - damage.downtime).recip(), 30.0 / 12.0), "{:?}", damage);
+		// and less than 60 needs no food at all
+		let damage = Upkeep::default().damage_taken(0.06, 900.0);
+		assert_eq!(damage.food_per_hour, 0.0);
+		assert_eq!(damage.downtime, 0.0);
+		assert!(close(damage.damage_per_hour, 54.0));
+	}
+
+	#[test]
+	fn a_damage_limit_rejects_what_goes_over_it() {
+		let damage = Upkeep::default().damage_taken(0.1, 3600.0);
+		assert!(Upkeep::default().allows(&damage));
+		assert!(Upkeep { max_damage_per_hour: Some(400.0), ..Upkeep::default() }.allows(&damage));
+		assert!(!Upkeep { max_damage_per_hour: Some(300.0), ..Upkeep::default() }.allows(&damage));
+	}
+}
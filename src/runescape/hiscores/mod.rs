@@ -0,0 +1,214 @@
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::io::Result as IoResult;
+
+use crate::runescape::{invalid_data, RunescapeInt};
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, PartialOrd, Ord)]
+pub enum Skill {
+	Overall,
+	Attack,
+	Defence,
+	Strength,
+	Hitpoints,
+	Ranged,
+	Prayer,
+	Magic,
+	Cooking,
+	Woodcutting,
+	Fletching,
+	Fishing,
+	Firemaking,
+	Crafting,
+	Smithing,
+	Mining,
+	Herblore,
+	Agility,
+	Thieving,
+	Slayer,
+	Farming,
+	Runecrafting,
+	Hunter,
+	Construction,
+	Sailing,
+}
+
+// the order skills appear in an `index_lite` response
+const SKILL_ORDER: [Skill; 25] = [
+	Skill::Overall,
+	Skill::Attack,
+	Skill::Defence,
+	Skill::Strength,
+	Skill::Hitpoints,
+	Skill::Ranged,
+	Skill::Prayer,
+	Skill::Magic,
+	Skill::Cooking,
+	Skill::Woodcutting,
+	Skill::Fletching,
+	Skill::Fishing,
+	Skill::Firemaking,
+	Skill::Crafting,
+	Skill::Smithing,
+	Skill::Mining,
+	Skill::Herblore,
+	Skill::Agility,
+	Skill::Thieving,
+	Skill::Slayer,
+	Skill::Farming,
+	Skill::Runecrafting,
+	Skill::Hunter,
+	Skill::Construction,
+	Skill::Sailing,
+];
+
+impl std::str::FromStr for Skill {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let lower = s.to_lowercase();
+		SKILL_ORDER.iter()
+			.find(|skill| format!("{:?}", skill).to_lowercase() == lower)
+			.copied()
+			.ok_or_else(|| format!("unknown skill {}", s))
+	}
+}
+
+// everything up to and including hunter has been on the hiscores for years
+const REQUIRED_SKILLS: usize = 23;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SkillEntry {
+	pub rank:  Option<RunescapeInt>,
+	pub level: RunescapeInt,
+	pub xp:    Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Hiscores {
+	skills: BTreeMap<Skill, SkillEntry>,
+}
+
+fn parse_field(line_number: usize, field: &str) -> IoResult<i64> {
+	field.trim().parse::<i64>().map_err(|e| {
+		invalid_data(format!("hiscores line {}: bad number {:?}: {}", line_number, field, e))
+	})
+}
+
+impl Hiscores {
+	pub fn parse(input: &str) -> IoResult<Self> {
+		let mut skills = BTreeMap::new();
+		let lines = input.lines()
+			.enumerate()
+			.map(|(i, line)| (i + 1, line.trim()))
+			.filter(|(_, line)| !line.is_empty());
+
+		for ((line_number, line), skill) in lines.zip(SKILL_ORDER.iter()) {
+			let fields: Vec<&str> = line.split(',').collect();
+			// activities (clue scrolls, bosses, ...) only have rank and score
+			if fields.len() != 3 {
+				break;
+			}
+			let rank = parse_field(line_number, fields[0])?;
+			let level = parse_field(line_number, fields[1])?;
+			let xp = parse_field(line_number, fields[2])?;
+			if level < 1 {
+				return Err(invalid_data(format!("hiscores line {}: bad level {} for {:?}", line_number, level, skill)));
+			}
+			// unranked skills are reported as -1
+			skills.insert(*skill, SkillEntry {
+				rank:  if rank < 0 { None } else { Some(rank as RunescapeInt) },
+				level: level as RunescapeInt,
+				xp:    if xp < 0 { None } else { Some(xp as u64) },
+			});
+		}
+
+		if skills.len() < REQUIRED_SKILLS {
+			return Err(invalid_data(format!("hiscores response only has {} skills, expected at least {}", skills.len(), REQUIRED_SKILLS)));
+		}
+
+		Ok(Self { skills })
+	}
+
+	pub fn read<R: Read>(mut reader: R) -> IoResult<Self> {
+		let mut input = String::new();
+		reader.read_to_string(&mut input)?;
+		Self::parse(&input)
+	}
+
+	// "-" reads from stdin
+	pub fn from_path(path: &str) -> IoResult<Self> {
+		match path {
+			"-" => Self::read(std::io::stdin()),
+			_   => Self::read(std::fs::File::open(path)?),
+		}
+	}
+
+	pub fn get(&self, skill: Skill) -> Option<&SkillEntry> {
+		self.skills.get(&skill)
+	}
+
+	pub fn level(&self, skill: Skill) -> Option<RunescapeInt> {
+		self.get(skill).map(|entry| entry.level)
+	}
+
+	pub fn xp(&self, skill: Skill) -> Option<u64> {
+		self.get(skill).and_then(|entry| entry.xp)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::runescape::graph::level::Melee;
+
+	// written by hand in the index_lite layout: a ranked main, an account
+	// with every skill unranked but its levels, and a cut off response
+	const MAIN: &str = include_str!("../../../tests/fixtures/hiscores/main.txt");
+	const UNRANKED: &str = include_str!("../../../tests/fixtures/hiscores/unranked.txt");
+	const TRUNCATED: &str = include_str!("../../../tests/fixtures/hiscores/truncated.txt");
+
+	#[test]
+	fn parses_skills_and_stops_at_activities() {
+		let hiscores = Hiscores::parse(MAIN).unwrap();
+		assert_eq!(hiscores.get(Skill::Attack), Some(&SkillEntry { rank: Some(98213), level: 78, xp: Some(1_683_249) }));
+		assert_eq!(hiscores.xp(Skill::Strength), Some(3_341_856));
+		assert_eq!(hiscores.level(Skill::Hunter), Some(62));
+		// sailing is unranked, and the activity rows after it aren't skills
+		assert_eq!(hiscores.get(Skill::Sailing), Some(&SkillEntry { rank: None, level: 1, xp: None }));
+		assert_eq!(hiscores.skills.len(), SKILL_ORDER.len());
+	}
+
+	#[test]
+	fn unranked_skills_keep_their_level() {
+		let hiscores = Hiscores::parse(UNRANKED).unwrap();
+		assert_eq!(hiscores.get(Skill::Hitpoints), Some(&SkillEntry { rank: None, level: 10, xp: None }));
+		assert_eq!(hiscores.xp(Skill::Attack), Some(13_922));
+	}
+
+	#[test]
+	fn truncated_response_is_an_error() {
+		let error = Hiscores::parse(TRUNCATED).unwrap_err();
+		assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+		assert_eq!(error.to_string(), "hiscores response only has 12 skills, expected at least 23");
+	}
+
+	#[test]
+	fn melee_starts_at_the_exact_xp() {
+		let melee = Melee::from_hiscores(&Hiscores::parse(MAIN).unwrap());
+		assert_eq!(melee.xp(Skill::Attack), 1_683_249);
+		assert_eq!(melee.xp(Skill::Strength), 3_341_856);
+		assert_eq!(melee.xp(Skill::Defence), 903_122);
+		assert_eq!(melee.xp(Skill::Hitpoints), 2_076_315);
+		assert_eq!(melee.level(Skill::Attack), 78);
+		assert_eq!(melee.level(Skill::Prayer), 60);
+	}
+
+	#[test]
+	fn unranked_hitpoints_come_from_combat_xp() {
+		let melee = Melee::from_hiscores(&Hiscores::parse(UNRANKED).unwrap());
+		// 10 hitpoints plus a third of the attack, strength and defence xp
+		assert_eq!(melee.xp(Skill::Hitpoints), 1_154 + (13_922 + 22_814 + 4_480) / 3);
+		assert_eq!(melee.level(Skill::Hitpoints), 31);
+	}
+}
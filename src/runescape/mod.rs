@@ -1,5 +1,27 @@
 pub mod osrsbox_db;
 pub mod graph;
 pub mod gear;
+pub mod hiscores;
+pub mod monster;
+pub mod unlocks;
 
-type RunescapeInt = i32;
+use std::io::{Error, ErrorKind};
+use std::io::Result as IoResult;
+
+use serde::de::DeserializeOwned;
+
+pub type RunescapeInt = i32;
+
+pub(crate) fn invalid_data(message: String) -> Error {
+	Error::new(ErrorKind::InvalidData, message)
+}
+
+// .toml files are read as toml, anything else as json
+pub(crate) fn from_path<T: DeserializeOwned>(path: &str) -> IoResult<T> {
+	let contents = std::fs::read_to_string(path)?;
+	if path.ends_with(".toml") {
+		toml::from_str(&contents).map_err(|e| invalid_data(format!("{}: {}", path, e)))
+	} else {
+		serde_json::from_str(&contents).map_err(|e| invalid_data(format!("{}: {}", path, e)))
+	}
+}
@@ -0,0 +1,174 @@
+use std::collections::BTreeSet;
+
+use crate::runescape::RunescapeInt;
+use crate::runescape::gear::special::DefenceReduction;
+use crate::runescape::osrsbox_db::types::AttackType;
+
+#[derive(Debug, Clone, Copy)]
+pub struct MonsterAttack {
+	pub max_hit:      RunescapeInt,
+	pub attack_level: RunescapeInt,
+	pub attack_bonus: RunescapeInt,
+	pub attack_type:  AttackType,
+	// in game ticks
+	pub attack_speed: RunescapeInt,
+}
+
+impl MonsterAttack {
+	pub fn max_attack_roll(&self) -> RunescapeInt {
+		(self.attack_level + 1 + 8) * (self.attack_bonus + 64)
+	}
+}
+
+impl Default for MonsterAttack {
+	fn default() -> Self {
+		Self {
+			max_hit:      1,
+			attack_level: 1,
+			attack_bonus: 0,
+			attack_type:  AttackType::Crush,
+			attack_speed: 4,
+		}
+	}
+}
+
+// what some weapons and amulets are better against
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub enum Attribute {
+	Undead,
+	Demon,
+	Dragon,
+	Kalphite,
+}
+
+impl std::str::FromStr for Attribute {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"undead"   => Ok(Attribute::Undead),
+			"demon"    => Ok(Attribute::Demon),
+			"dragon"   => Ok(Attribute::Dragon),
+			"kalphite" => Ok(Attribute::Kalphite),
+			_          => Err(format!("unknown target attribute {}, expected undead, demon, dragon or kalphite", s)),
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct Target {
+	pub name:           String,
+	pub hitpoints:      RunescapeInt,
+	pub defence_level:  RunescapeInt,
+	pub defence_stab:   RunescapeInt,
+	pub defence_slash:  RunescapeInt,
+	pub defence_crush:  RunescapeInt,
+	// how the target fights back, None ignores the damage taken
+	pub attack:         Option<MonsterAttack>,
+	pub attributes:     BTreeSet<Attribute>,
+	// whether the target is the player's slayer task
+	pub on_task:        bool,
+	// specials thrown at the start of a kill whenever the bar holds them all, in order
+	pub defence_reductions: Vec<DefenceReduction>,
+}
+
+impl Target {
+	pub fn sand_crab() -> Self {
+		Self {
+			name:          "Sand Crab".into(),
+			hitpoints:     60,
+			defence_level: 1,
+			defence_stab:  0,
+			defence_slash: 0,
+			defence_crush: 0,
+			// they hit for at most 1, which hitpoints regeneration outpaces
+			attack:        None,
+			attributes:    BTreeSet::new(),
+			on_task:       false,
+			defence_reductions: Vec::new(),
+		}
+	}
+
+	pub fn is(&self, attribute: Attribute) -> bool {
+		self.attributes.contains(&attribute)
+	}
+
+	// only damage up to the hitpoints gives xp
+	pub fn xp_per_kill(&self) -> f64 {
+		self.hitpoints as f64 * 4.0
+	}
+
+	pub fn defence_bonus(&self, attack_type: AttackType) -> RunescapeInt {
+		match attack_type {
+			AttackType::Stab  => self.defence_stab,
+			AttackType::Slash => self.defence_slash,
+			AttackType::Crush => self.defence_crush,
+			_                 => 0,
+		}
+	}
+
+	pub fn max_defence_roll(&self, attack_type: AttackType) -> RunescapeInt {
+		self.defence_roll(self.defence_level, attack_type)
+	}
+
+	// the max defence roll once its defence has been lowered to `defence_level`
+	pub fn defence_roll(&self, defence_level: RunescapeInt, attack_type: AttackType) -> RunescapeInt {
+		(defence_level + 1 + 8) * (self.defence_bonus(attack_type) + 64)
+	}
+}
+
+// either a known monster, or key=value stats such as hp=60,defence=1,slash=20.
+// any of max-hit, attack, attack-bonus, attack-type or attack-speed makes the
+// target fight back, and bare words like undead or demon are its attributes
+impl std::str::FromStr for Target {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		if s == "sand-crab" || s == "sand crab" {
+			return Ok(Target::sand_crab());
+		}
+
+		let mut target = Target {
+			name:          "custom target".into(),
+			hitpoints:     1,
+			defence_level: 1,
+			defence_stab:  0,
+			defence_slash: 0,
+			defence_crush: 0,
+			attack:        None,
+			attributes:    BTreeSet::new(),
+			on_task:       false,
+			defence_reductions: Vec::new(),
+		};
+		for pair in s.split(',') {
+			if !pair.contains('=') {
+				target.attributes.insert(pair.trim().parse()?);
+				continue;
+			}
+			let mut parts = pair.splitn(2, '=');
+			let key = parts.next().unwrap().trim();
+			let raw = parts.next()
+				.map(str::trim)
+				.ok_or_else(|| format!("bad target stat {}, expected key=value", pair))?;
+			if key == "attack-type" {
+				target.attack.get_or_insert_with(MonsterAttack::default).attack_type = raw.parse()?;
+				continue;
+			}
+			let value = raw.parse::<RunescapeInt>()
+				.map_err(|_| format!("bad target stat {}, expected key=number", pair))?;
+			match key {
+				"hp" | "hitpoints" => target.hitpoints = value,
+				"defence"          => target.defence_level = value,
+				"stab"             => target.defence_stab = value,
+				"slash"            => target.defence_slash = value,
+				"crush"            => target.defence_crush = value,
+				"max-hit"          => target.attack.get_or_insert_with(MonsterAttack::default).max_hit = value,
+				"attack"           => target.attack.get_or_insert_with(MonsterAttack::default).attack_level = value,
+				"attack-bonus"     => target.attack.get_or_insert_with(MonsterAttack::default).attack_bonus = value,
+				"attack-speed"     => target.attack.get_or_insert_with(MonsterAttack::default).attack_speed = value,
+				_                  => return Err(format!("unknown target stat {}", key)),
+			}
+		}
+		Ok(target)
+	}
+}
@@ -1,15 +1,17 @@
+pub mod overlay;
 pub mod types;
+pub mod weapon_type;
 
 use std::collections::BTreeMap;
 use ureq;
 use serde_json;
 use serde_json::{Map, Value};
 use std::io::Result as IoResult;
-use super::RunescapeInt;
+use super::{invalid_data, RunescapeInt};
 
 impl From<types::Slot> for String {
 	fn from(slot: types::Slot) -> Self {
-		serde_json::to_value(&slot).unwrap().as_str().unwrap().into()
+		serde_json::to_value(slot).unwrap().as_str().unwrap().into()
 	}
 }
 
@@ -18,7 +20,7 @@ fn raw_request(slot: types::Slot) -> IoResult<Map<String, Value>> {
 	let url = format!("https://www.osrsbox.com/osrsbox-db/items-json-slot/items-{}.json", slot);
 	match ureq::get(&url).call().into_json()? {
 		Value::Object(m) => Ok(m),
-		_                => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "non-object response from osrsbox")),
+		_                => Err(invalid_data("non-object response from osrsbox".into())),
 	}
 }
 
@@ -26,12 +28,11 @@ fn map_to_items(map: Map<String, Value>) -> IoResult<BTreeMap<RunescapeInt, type
 	let mut m = BTreeMap::new();
 	// let mut v = Vec::with_capacity(map.len());
 	for (k, v) in map.into_iter() {
-		let k = k.parse::<RunescapeInt>();
-		let k = match k {
-			Ok(k) => k,
-			Err(e) => return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+		let k = match k.parse::<RunescapeInt>() {
+			Ok(id) => id,
+			Err(e) => return Err(invalid_data(format!("bad item id {}: {}", k, e))),
 		};
-		if let Some(_) = m.insert(k, serde_json::from_value(v)?) {
+		if m.insert(k, serde_json::from_value(v)?).is_some() {
 			unreachable!();
 		}
 	}
@@ -41,3 +42,70 @@ fn map_to_items(map: Map<String, Value>) -> IoResult<BTreeMap<RunescapeInt, type
 pub fn request(slot: types::Slot) -> IoResult<BTreeMap<RunescapeInt, types::Item>> {
 	map_to_items(raw_request(slot)?)
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use types::{Slot, WeaponType};
+
+	// two entries of items-weapon.json written out field for field in the
+	// osrsbox schema, with the base64 icons cut short
+	const WEAPONS: &str = include_str!("../../../tests/fixtures/osrsbox/items-weapon.json");
+
+	fn items() -> BTreeMap<RunescapeInt, types::Item> {
+		map_to_items(serde_json::from_str(WEAPONS).unwrap()).unwrap()
+	}
+
+	#[test]
+	fn full_schema_deserializes() {
+		let items = items();
+		let scimitar = &items[&4587];
+		assert_eq!(scimitar.name, "Dragon scimitar");
+		assert_eq!(scimitar.members, Some(true));
+		assert!(scimitar.tradeable_on_ge);
+		assert!(!scimitar.quest_item);
+		assert_eq!(scimitar.weight, Some(1.814));
+		assert_eq!(scimitar.release_date.as_deref(), Some("2005-03-29"));
+		assert_eq!(scimitar.wiki_url.as_deref(), Some("https://oldschool.runescape.wiki/w/Dragon_scimitar"));
+		assert_eq!(scimitar.linked_id_noted, Some(4588));
+		assert_eq!(scimitar.highalch, Some(60000));
+		assert_eq!(scimitar.equipment.slot, Slot::Weapon);
+		assert_eq!(scimitar.equipment.requirements.as_ref().and_then(|stats| stats.attack), Some(60));
+		let weapon = scimitar.weapon.as_ref().unwrap();
+		assert_eq!(weapon.weapon_type, WeaponType::SlashSword);
+		assert_eq!(weapon.stances.len(), 4);
+
+		let sword = &items[&1277];
+		assert_eq!(sword.members, Some(false));
+		assert!(sword.equipment.requirements.is_none());
+	}
+
+	#[test]
+	fn missing_fields_default() {
+		let item: types::Item = serde_json::from_value(serde_json::json!({
+			"id": 1277,
+			"name": "Bronze sword",
+			"equipment": {
+				"attack_stab": 4, "attack_slash": 3, "attack_crush": -2, "attack_magic": 0, "attack_ranged": 0,
+				"defence_stab": 0, "defence_slash": 2, "defence_crush": 1, "defence_magic": 0, "defence_ranged": 0,
+				"melee_strength": 5, "ranged_strength": 0, "magic_damage": 0, "prayer": 0,
+				"slot": "weapon",
+				"requirements": null,
+			},
+		})).unwrap();
+		// membership isn't guessed
+		assert_eq!(item.members, None);
+		assert!(!item.incomplete);
+		assert!(!item.tradeable);
+		assert!(!item.stackable);
+		assert!(!item.noteable);
+		assert!(!item.equipable);
+		assert!(!item.quest_item);
+		assert!(!item.duplicate);
+		assert_eq!(item.stacked, None);
+		assert_eq!(item.linked_id_noted, None);
+		assert_eq!(item.weight, None);
+		assert_eq!(item.release_date, None);
+		assert!(item.weapon.is_none());
+	}
+}
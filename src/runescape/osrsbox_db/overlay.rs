@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+use std::io::Result as IoResult;
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use super::types::{Equipment, Item};
+use crate::runescape::{from_path, invalid_data, RunescapeInt};
+
+// local fixes on top of osrsbox: whole new items, replacement equipment
+// fields for existing ids, and ids to drop
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Overlay {
+	#[serde(default)]
+	pub add:       Vec<Value>,
+	#[serde(default, rename = "override")]
+	pub overrides: BTreeMap<String, Map<String, Value>>,
+	#[serde(default)]
+	pub remove:    Vec<RunescapeInt>,
+}
+
+impl Overlay {
+	// .toml files are read as toml, anything else as json
+	pub fn from_path(path: &str) -> IoResult<Self> {
+		from_path(path)
+	}
+
+	pub fn apply(&self, gear: &mut BTreeMap<RunescapeInt, Item>) -> IoResult<()> {
+		for (i, value) in self.add.iter().enumerate() {
+			let name = value.get("name").and_then(Value::as_str).unwrap_or("unnamed").to_string();
+			let item: Item = serde_json::from_value(value.clone())
+				.map_err(|e| invalid_data(format!("overlay add #{} ({}): {}", i + 1, name, e)))?;
+			if gear.contains_key(&item.id) {
+				return Err(invalid_data(format!("overlay add #{} ({}): id {} already exists, override it instead", i + 1, name, item.id)));
+			}
+			gear.insert(item.id, item);
+		}
+
+		for (key, fields) in &self.overrides {
+			let id = key.parse::<RunescapeInt>()
+				.map_err(|_| invalid_data(format!("overlay override {}: not an item id", key)))?;
+			let item = gear.get_mut(&id)
+				.ok_or_else(|| invalid_data(format!("overlay override {}: no item with that id", id)))?;
+			let mut equipment = match serde_json::to_value(&item.equipment)? {
+				Value::Object(equipment) => equipment,
+				_                        => unreachable!(),
+			};
+			for (field, value) in fields {
+				if !equipment.contains_key(field) {
+					return Err(invalid_data(format!("overlay override {} ({}): unknown equipment field {}", id, item.name, field)));
+				}
+				equipment.insert(field.clone(), value.clone());
+			}
+			item.equipment = serde_json::from_value::<Equipment>(Value::Object(equipment))
+				.map_err(|e| invalid_data(format!("overlay override {} ({}): {}", id, item.name, e)))?;
+		}
+
+		for id in &self.remove {
+			if gear.remove(id).is_none() {
+				return Err(invalid_data(format!("overlay remove {}: no item with that id", id)));
+			}
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const WEAPONS: &str = include_str!("../../../tests/fixtures/osrsbox/items-weapon.json");
+
+	fn gear() -> BTreeMap<RunescapeInt, Item> {
+		super::super::map_to_items(serde_json::from_str(WEAPONS).unwrap()).unwrap()
+	}
+
+	fn apply(overlay: &str) -> Result<BTreeMap<RunescapeInt, Item>, String> {
+		let overlay: Overlay = toml::from_str(overlay).unwrap();
+		let mut gear = gear();
+		overlay.apply(&mut gear).map_err(|e| e.to_string())?;
+		Ok(gear)
+	}
+
+	#[test]
+	fn overrides_change_only_the_named_fields() {
+		let original = gear();
+		let gear = apply(r#"
+			[override.4587]
+			melee_strength = 70
+			requirements = { attack = 65 }
+		"#).unwrap();
+		let (before, after) = (&original[&4587], &gear[&4587]);
+		assert_eq!(after.equipment.melee_strength, 70);
+		assert_eq!(after.equipment.requirements.as_ref().and_then(|stats| stats.attack), Some(65));
+		let expected = Equipment { melee_strength: 70, requirements: after.equipment.requirements.clone(), ..before.equipment.clone() };
+		assert_eq!(after.equipment, expected);
+		assert_eq!(after.name, before.name);
+		// and nothing else
+		assert_eq!(gear[&1277].equipment, original[&1277].equipment);
+	}
+
+	#[test]
+	fn added_ids_must_be_new() {
+		let error = apply(r#"
+			[[add]]
+			id = 1277
+			name = "Bronze sword"
+			equipment = { attack_stab = 4, attack_slash = 3, attack_crush = -2, attack_magic = 0, attack_ranged = 0, defence_stab = 0, defence_slash = 2, defence_crush = 1, defence_magic = 0, defence_ranged = 0, melee_strength = 5, ranged_strength = 0, magic_damage = 0, prayer = 0, slot = "weapon" }
+		"#).unwrap_err();
+		assert!(error.contains("overlay add #1 (Bronze sword)") && error.contains("id 1277 already exists"), "{}", error);
+	}
+
+	#[test]
+	fn overrides_need_a_known_id() {
+		let error = apply("[override.1205]\nmelee_strength = 4\n").unwrap_err();
+		assert!(error.contains("overlay override 1205: no item with that id"), "{}", error);
+		let error = apply("[override.dagger]\nmelee_strength = 4\n").unwrap_err();
+		assert!(error.contains("overlay override dagger: not an item id"), "{}", error);
+	}
+
+	#[test]
+	fn overrides_need_a_known_field() {
+		let error = apply("[override.1277]\nstrength = 6\n").unwrap_err();
+		assert!(error.contains("overlay override 1277 (Bronze sword): unknown equipment field strength"), "{}", error);
+	}
+
+	#[test]
+	fn removed_ids_must_exist() {
+		assert!(!apply("remove = [1277]").unwrap().contains_key(&1277));
+		let error = apply("remove = [1205]").unwrap_err();
+		assert!(error.contains("overlay remove 1205: no item with that id"), "{}", error);
+	}
+}
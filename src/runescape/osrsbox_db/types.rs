@@ -1,15 +1,57 @@
 use serde::{Serialize, Deserialize};
 use super::super::RunescapeInt;
+pub use super::weapon_type::{stances_from_osrsbox, StanceEffect, StyleBonus, WeaponType, XpSplit};
 
+// the base64 `icon` is left out on purpose, nothing here draws items
 #[derive(Deserialize, Debug, Clone)]
 pub struct Item {
-	pub id:        RunescapeInt,
-	pub name:      String,
-	pub equipment: Equipment,
-	pub weapon:    Option<Weapon>,
+	pub id:                    RunescapeInt,
+	pub name:                  String,
+	#[serde(default)]
+	pub incomplete:            bool,
+	// `None` when osrsbox doesn't say, which f2p worlds treat as members only
+	pub members:               Option<bool>,
+	#[serde(default)]
+	pub tradeable:             bool,
+	#[serde(default)]
+	pub tradeable_on_ge:       bool,
+	#[serde(default)]
+	pub stackable:             bool,
+	pub stacked:               Option<RunescapeInt>,
+	#[serde(default)]
+	pub noted:                 bool,
+	#[serde(default)]
+	pub noteable:              bool,
+	pub linked_id_item:        Option<RunescapeInt>,
+	pub linked_id_noted:       Option<RunescapeInt>,
+	pub linked_id_placeholder: Option<RunescapeInt>,
+	#[serde(default)]
+	pub placeholder:           bool,
+	#[serde(default)]
+	pub equipable:             bool,
+	#[serde(default)]
+	pub equipable_by_player:   bool,
+	#[serde(default)]
+	pub equipable_weapon:      bool,
+	#[serde(default)]
+	pub cost:                  RunescapeInt,
+	pub lowalch:               Option<RunescapeInt>,
+	pub highalch:              Option<RunescapeInt>,
+	pub weight:                Option<f64>,
+	pub buy_limit:             Option<RunescapeInt>,
+	#[serde(default)]
+	pub quest_item:            bool,
+	pub release_date:          Option<String>,
+	#[serde(default)]
+	pub duplicate:             bool,
+	pub examine:               Option<String>,
+	pub wiki_name:             Option<String>,
+	pub wiki_url:              Option<String>,
+	pub equipment:             Equipment,
+	pub weapon:                Option<Weapon>,
 }
 
-#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct Equipment {
 	pub attack_stab:     RunescapeInt,
 	pub attack_slash:    RunescapeInt,
@@ -47,7 +89,17 @@ pub enum Slot {
 	Weapon,
 }
 
-#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+impl Slot {
+	// two-handed weapons go in the weapon slot, and leave the shield slot empty
+	pub fn worn_in(self) -> Self {
+		match self {
+			Slot::TwoH => Slot::Weapon,
+			slot       => slot,
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct Stats {
 	pub attack:    Option<RunescapeInt>,
 	pub strength:  Option<RunescapeInt>,
@@ -73,10 +125,24 @@ impl Stats {
 #[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Weapon {
 	pub attack_speed: RunescapeInt,
-	pub weapon_type:  String,
+	pub weapon_type:  WeaponType,
 	pub stances:      Vec<Stance>,
 }
 
+impl Weapon {
+	pub fn stance_table(&self) -> Vec<StanceEffect> {
+		match &self.weapon_type {
+			WeaponType::Other(_) => stances_from_osrsbox(&self.stances),
+			weapon_type          => weapon_type.stances(),
+		}
+	}
+
+	// the first stance that attacks with `attack_type` and trains like `attack_style`
+	pub fn stance(&self, attack_type: AttackType, attack_style: AttackStyle) -> Option<StanceEffect> {
+		self.stance_table().into_iter().find(|stance| stance.attack_type == Some(attack_type) && stance.attack_style == attack_style)
+	}
+}
+
 #[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Stance {
 	pub combat_style: String,
@@ -84,7 +150,7 @@ pub struct Stance {
 	pub attack_style: Option<AttackStyle>,
 }
 
-#[derive(Deserialize, Debug, PartialEq, Eq, Copy, Clone, PartialOrd, Ord)]
+#[derive(Deserialize, Debug, PartialEq, Eq, Copy, Clone, PartialOrd, Ord, Hash)]
 #[serde(rename_all = "lowercase")]
 pub enum AttackType {
 	Crush,
@@ -95,7 +161,7 @@ pub enum AttackType {
 	Stab,
 }
 
-#[derive(Deserialize, Debug, PartialEq, Eq, Copy, Clone, PartialOrd, Ord)]
+#[derive(Deserialize, Debug, PartialEq, Eq, Copy, Clone, PartialOrd, Ord, Hash)]
 #[serde(rename_all = "lowercase")]
 pub enum AttackStyle {
 	Accurate,
@@ -103,4 +169,37 @@ pub enum AttackStyle {
 	Controlled,
 	Defensive,
 	Magic,
+	// ranged and magic stances, osrsbox leaves their style out
+	Rapid,
+	Longrange,
+}
+
+// the same lowercase names osrsbox uses
+fn from_osrsbox_name<'de, T: Deserialize<'de>>(s: &str, kind: &str) -> Result<T, String> {
+	T::deserialize(serde_json::Value::String(s.to_lowercase()))
+		.map_err(|_| format!("unknown {} {}", kind, s))
+}
+
+impl std::str::FromStr for AttackType {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		from_osrsbox_name(s, "attack type")
+	}
+}
+
+impl std::str::FromStr for AttackStyle {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		from_osrsbox_name(s, "attack style")
+	}
+}
+
+impl std::str::FromStr for Slot {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		from_osrsbox_name(s, "slot")
+	}
 }
@@ -0,0 +1,227 @@
+use serde::Deserialize;
+
+use super::super::RunescapeInt;
+use super::super::hiscores::Skill;
+use super::types::{AttackStyle, AttackType, Stance};
+
+// osrsbox's weapon types, anything it adds later ends up in Other
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "String")]
+pub enum WeaponType {
+	TwoHandedSword,
+	Axe,
+	Banner,
+	BladedStaff,
+	Bludgeon,
+	Blunt,
+	Bulwark,
+	Claw,
+	Partisan,
+	Pickaxe,
+	Polearm,
+	Polestaff,
+	Scythe,
+	SlashSword,
+	Spear,
+	Spiked,
+	StabSword,
+	Staff,
+	Unarmed,
+	Whip,
+	Bow,
+	Crossbow,
+	Thrown,
+	Chinchompas,
+	Salamander,
+	PoweredStaff,
+	Other(String),
+}
+
+impl From<String> for WeaponType {
+	fn from(s: String) -> Self {
+		match s.as_str() {
+			"2h_sword"      => WeaponType::TwoHandedSword,
+			"axe"           => WeaponType::Axe,
+			"banner"        => WeaponType::Banner,
+			"bladed_staff"  => WeaponType::BladedStaff,
+			"bludgeon"      => WeaponType::Bludgeon,
+			"blunt"         => WeaponType::Blunt,
+			"bulwark"       => WeaponType::Bulwark,
+			"claw"          => WeaponType::Claw,
+			"partisan"      => WeaponType::Partisan,
+			"pickaxe"       => WeaponType::Pickaxe,
+			"polearm"       => WeaponType::Polearm,
+			"polestaff"     => WeaponType::Polestaff,
+			"scythe"        => WeaponType::Scythe,
+			"slash_sword"   => WeaponType::SlashSword,
+			"spear"         => WeaponType::Spear,
+			"spiked"        => WeaponType::Spiked,
+			"stab_sword"    => WeaponType::StabSword,
+			"staff"         => WeaponType::Staff,
+			"unarmed"       => WeaponType::Unarmed,
+			"whip"          => WeaponType::Whip,
+			"bow"           => WeaponType::Bow,
+			"crossbow"      => WeaponType::Crossbow,
+			"thrown"        => WeaponType::Thrown,
+			"chinchompas"   => WeaponType::Chinchompas,
+			"salamander"    => WeaponType::Salamander,
+			"powered_staff" => WeaponType::PoweredStaff,
+			_               => WeaponType::Other(s),
+		}
+	}
+}
+
+// invisible levels a stance adds to the effective attack, strength and
+// defence levels. ranged stances put their accuracy and damage bonus in
+// attack and strength
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone, Default)]
+pub struct StyleBonus {
+	pub attack:   RunescapeInt,
+	pub strength: RunescapeInt,
+	pub defence:  RunescapeInt,
+}
+
+impl StyleBonus {
+	pub fn melee(style: AttackStyle) -> Self {
+		match style {
+			AttackStyle::Accurate   => Self { attack: 3, ..Self::default() },
+			AttackStyle::Aggressive => Self { strength: 3, ..Self::default() },
+			AttackStyle::Defensive  => Self { defence: 3, ..Self::default() },
+			AttackStyle::Controlled => Self { attack: 1, strength: 1, defence: 1 },
+			_                       => Self::default(),
+		}
+	}
+}
+
+// the skills a stance's combat xp goes to
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum XpSplit {
+	Attack,
+	Strength,
+	Defence,
+	// a third each to attack, strength and defence
+	Shared,
+	Ranged,
+	// half to ranged, half to defence
+	RangedDefence,
+	Magic,
+	MagicDefence,
+}
+
+impl XpSplit {
+	pub fn melee(style: AttackStyle) -> Self {
+		match style {
+			AttackStyle::Accurate   => XpSplit::Attack,
+			AttackStyle::Aggressive => XpSplit::Strength,
+			AttackStyle::Defensive  => XpSplit::Defence,
+			_                       => XpSplit::Shared,
+		}
+	}
+
+	// every skill gets an equal share
+	pub fn skills(self) -> &'static [Skill] {
+		match self {
+			XpSplit::Attack        => &[Skill::Attack],
+			XpSplit::Strength      => &[Skill::Strength],
+			XpSplit::Defence       => &[Skill::Defence],
+			XpSplit::Shared        => &[Skill::Attack, Skill::Strength, Skill::Defence],
+			XpSplit::Ranged        => &[Skill::Ranged],
+			XpSplit::RangedDefence => &[Skill::Ranged, Skill::Defence],
+			XpSplit::Magic         => &[Skill::Magic],
+			XpSplit::MagicDefence  => &[Skill::Magic, Skill::Defence],
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StanceEffect {
+	pub combat_style: String,
+	// None for ranged and magic stances
+	pub attack_type:  Option<AttackType>,
+	pub attack_style: AttackStyle,
+	pub style_bonus:  StyleBonus,
+	pub xp:           XpSplit,
+	// ticks added to the weapon's attack speed, rapid takes one off
+	pub speed:        RunescapeInt,
+}
+
+fn melee(combat_style: &str, attack_type: AttackType, attack_style: AttackStyle) -> StanceEffect {
+	StanceEffect {
+		combat_style: combat_style.into(),
+		attack_type: Some(attack_type),
+		attack_style,
+		style_bonus: StyleBonus::melee(attack_style),
+		xp: XpSplit::melee(attack_style),
+		speed: 0,
+	}
+}
+
+fn ranged(combat_style: &str, attack_style: AttackStyle) -> StanceEffect {
+	let (style_bonus, xp, speed) = match attack_style {
+		AttackStyle::Accurate => (StyleBonus { attack: 3, strength: 3, defence: 0 }, XpSplit::Ranged, 0),
+		AttackStyle::Rapid    => (StyleBonus::default(), XpSplit::Ranged, -1),
+		_                     => (StyleBonus { defence: 3, ..StyleBonus::default() }, XpSplit::RangedDefence, 0),
+	};
+	StanceEffect { combat_style: combat_style.into(), attack_type: None, attack_style, style_bonus, xp, speed }
+}
+
+fn magic(combat_style: &str, attack_style: AttackStyle) -> StanceEffect {
+	let (style_bonus, xp) = match attack_style {
+		AttackStyle::Longrange => (StyleBonus { defence: 3, ..StyleBonus::default() }, XpSplit::MagicDefence),
+		_                      => (StyleBonus { attack: 2, ..StyleBonus::default() }, XpSplit::Magic),
+	};
+	StanceEffect { combat_style: combat_style.into(), attack_type: None, attack_style, style_bonus, xp, speed: 0 }
+}
+
+impl WeaponType {
+	// the stances in the order the combat tab lists them. Other has no table
+	// of its own and is read from osrsbox's stances instead
+	pub fn stances(&self) -> Vec<StanceEffect> {
+		use AttackStyle::*;
+		use AttackType::*;
+
+		match self {
+			WeaponType::TwoHandedSword => vec![melee("chop", Slash, Accurate), melee("slash", Slash, Aggressive), melee("smash", Crush, Aggressive), melee("block", Slash, Defensive)],
+			WeaponType::Axe            => vec![melee("chop", Slash, Accurate), melee("hack", Slash, Aggressive), melee("smash", Crush, Aggressive), melee("block", Slash, Defensive)],
+			WeaponType::Banner         => vec![melee("lunge", Stab, Accurate), melee("swipe", Slash, Aggressive), melee("pound", Crush, Controlled), melee("block", Stab, Defensive)],
+			WeaponType::BladedStaff    => vec![melee("jab", Stab, Accurate), melee("swipe", Slash, Aggressive), melee("fend", Crush, Defensive)],
+			WeaponType::Bludgeon       => vec![melee("pound", Crush, Aggressive), melee("pummel", Crush, Aggressive), melee("smash", Crush, Aggressive)],
+			WeaponType::Blunt          => vec![melee("pound", Crush, Accurate), melee("pummel", Crush, Aggressive), melee("block", Crush, Defensive)],
+			// the bulwark's block stance can't attack at all
+			WeaponType::Bulwark        => vec![melee("pummel", Crush, Accurate)],
+			WeaponType::Claw           => vec![melee("chop", Slash, Accurate), melee("slash", Slash, Aggressive), melee("lunge", Stab, Controlled), melee("block", Slash, Defensive)],
+			WeaponType::Partisan       => vec![melee("stab", Stab, Accurate), melee("lunge", Stab, Aggressive), melee("pound", Crush, Aggressive), melee("block", Stab, Defensive)],
+			WeaponType::Pickaxe        => vec![melee("spike", Stab, Accurate), melee("impale", Stab, Aggressive), melee("smash", Crush, Aggressive), melee("block", Stab, Defensive)],
+			WeaponType::Polearm        => vec![melee("jab", Stab, Controlled), melee("swipe", Slash, Aggressive), melee("fend", Stab, Defensive)],
+			WeaponType::Polestaff      => vec![melee("bash", Crush, Accurate), melee("pound", Crush, Aggressive), melee("block", Crush, Defensive)],
+			WeaponType::Scythe         => vec![melee("reap", Slash, Accurate), melee("chop", Slash, Aggressive), melee("jab", Crush, Aggressive), melee("block", Slash, Defensive)],
+			WeaponType::SlashSword     => vec![melee("chop", Slash, Accurate), melee("slash", Slash, Aggressive), melee("lunge", Stab, Controlled), melee("block", Slash, Defensive)],
+			WeaponType::Spear          => vec![melee("lunge", Stab, Controlled), melee("swipe", Slash, Controlled), melee("pound", Crush, Controlled), melee("block", Stab, Defensive)],
+			WeaponType::Spiked         => vec![melee("pound", Crush, Accurate), melee("pummel", Crush, Aggressive), melee("spike", Stab, Controlled), melee("block", Crush, Defensive)],
+			WeaponType::StabSword      => vec![melee("stab", Stab, Accurate), melee("lunge", Stab, Aggressive), melee("slash", Slash, Aggressive), melee("block", Stab, Defensive)],
+			WeaponType::Staff          => vec![melee("bash", Crush, Accurate), melee("pound", Crush, Aggressive), melee("focus", Crush, Defensive)],
+			WeaponType::Unarmed        => vec![melee("punch", Crush, Accurate), melee("kick", Crush, Aggressive), melee("block", Crush, Defensive)],
+			WeaponType::Whip           => vec![melee("flick", Slash, Accurate), melee("lash", Slash, Controlled), melee("deflect", Slash, Defensive)],
+			WeaponType::Bow | WeaponType::Crossbow | WeaponType::Thrown => {
+				vec![ranged("accurate", Accurate), ranged("rapid", Rapid), ranged("longrange", Longrange)]
+			},
+			WeaponType::Chinchompas    => vec![ranged("short fuse", Accurate), ranged("medium fuse", Rapid), ranged("long fuse", Longrange)],
+			WeaponType::Salamander     => vec![melee("scorch", Slash, Aggressive), ranged("flare", Accurate), magic("blaze", Accurate)],
+			WeaponType::PoweredStaff   => vec![magic("accurate", Accurate), magic("longrange", Longrange)],
+			WeaponType::Other(_)       => Vec::new(),
+		}
+	}
+}
+
+// osrsbox's own stances, for weapon types without a table. only the melee
+// ones say enough to go by
+pub fn stances_from_osrsbox(stances: &[Stance]) -> Vec<StanceEffect> {
+	stances.iter()
+		.filter_map(|stance| match (stance.attack_type?, stance.attack_style?) {
+			(attack_type @ (AttackType::Stab | AttackType::Slash | AttackType::Crush), attack_style) => {
+				Some(melee(&stance.combat_style, attack_type, attack_style))
+			},
+			_ => None,
+		})
+		.collect()
+}
@@ -0,0 +1,193 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Result as IoResult;
+
+use serde::Deserialize;
+
+use crate::runescape::{from_path, invalid_data, RunescapeInt};
+
+// the table that ships with the planner, see data/unlocks.toml
+const BUILTIN_TABLE: &str = include_str!("../../../data/unlocks.toml");
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Requirement {
+	Quest(String),
+	Diary(String),
+	// minigames, bosses and anything else that isn't a quest or diary
+	Unlock(String),
+}
+
+impl std::fmt::Display for Requirement {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Requirement::Quest(name)  => write!(f, "quest {}", name),
+			Requirement::Diary(name)  => write!(f, "diary {}", name),
+			Requirement::Unlock(name) => write!(f, "{}", name),
+		}
+	}
+}
+
+// a name covers every item it starts, so poisoned, ornament and trimmed
+// variants count too. an id covers that one item
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+struct TableEntry {
+	id: Option<RunescapeInt>,
+	name: Option<String>,
+	#[serde(default)]
+	quests: Vec<String>,
+	#[serde(default)]
+	diaries: Vec<String>,
+	#[serde(default)]
+	unlocks: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+struct TableFile {
+	#[serde(default)]
+	item: Vec<TableEntry>,
+}
+
+// everything that has to be done before an item can be worn, by item id
+// and by name prefix
+#[derive(Debug, Clone, Default)]
+pub struct UnlockTable {
+	ids: BTreeMap<RunescapeInt, Vec<Requirement>>,
+	names: Vec<(String, Vec<Requirement>)>,
+}
+
+impl UnlockTable {
+	fn from_file(file: TableFile) -> Result<Self, String> {
+		let mut table = Self::default();
+		for (i, entry) in file.item.into_iter().enumerate() {
+			let requirements: Vec<Requirement> = entry.quests.into_iter().map(Requirement::Quest)
+				.chain(entry.diaries.into_iter().map(Requirement::Diary))
+				.chain(entry.unlocks.into_iter().map(Requirement::Unlock))
+				.collect();
+			match (entry.id, entry.name) {
+				(Some(id), _)   => table.ids.entry(id).or_default().extend(requirements),
+				(None, Some(name)) => table.names.push((name, requirements)),
+				(None, None)    => return Err(format!("unlock table item #{} needs an id or a name", i + 1)),
+			}
+		}
+		Ok(table)
+	}
+
+	pub fn builtin() -> Self {
+		Self::from_file(toml::from_str(BUILTIN_TABLE).expect("the builtin unlock table is valid")).expect("the builtin unlock table is valid")
+	}
+
+	pub fn from_path(path: &str) -> IoResult<Self> {
+		Self::from_file(from_path(path)?).map_err(|e| invalid_data(format!("{}: {}", path, e)))
+	}
+
+	// the requirements of the item's id and of every name it starts with
+	pub fn requirements(&self, id: RunescapeInt, name: &str) -> Vec<Requirement> {
+		let mut requirements: Vec<Requirement> = Vec::new();
+		let by_name = self.names.iter()
+			.filter(|(prefix, _)| name.starts_with(prefix.as_str()))
+			.flat_map(|(_, requirements)| requirements);
+		for requirement in self.ids.get(&id).into_iter().flatten().chain(by_name) {
+			if !requirements.contains(requirement) {
+				requirements.push(requirement.clone());
+			}
+		}
+		requirements
+	}
+}
+
+// what a player has finished. names are compared without regard to case
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+	#[serde(default)]
+	pub quests: BTreeSet<String>,
+	#[serde(default)]
+	pub diaries: BTreeSet<String>,
+	#[serde(default)]
+	pub unlocks: BTreeSet<String>,
+}
+
+impl Profile {
+	pub fn from_path(path: &str) -> IoResult<Self> {
+		from_path(path)
+	}
+
+	pub fn has(&self, requirement: &Requirement) -> bool {
+		let (done, name) = match requirement {
+			Requirement::Quest(name)  => (&self.quests, name),
+			Requirement::Diary(name)  => (&self.diaries, name),
+			Requirement::Unlock(name) => (&self.unlocks, name),
+		};
+		done.iter().any(|done| done.eq_ignore_ascii_case(name))
+	}
+
+	// the requirements of an item this profile is still missing
+	pub fn missing(&self, table: &UnlockTable, id: RunescapeInt, name: &str) -> Vec<Requirement> {
+		table.requirements(id, name).iter()
+			.filter(|requirement| !self.has(requirement))
+			.cloned()
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn table(toml: &str) -> Result<UnlockTable, String> {
+		UnlockTable::from_file(toml::from_str(toml).unwrap())
+	}
+
+	#[test]
+	fn names_lock_every_variant() {
+		let table = UnlockTable::builtin();
+		let lost_city = vec![Requirement::Quest("Lost City".into())];
+		assert_eq!(table.requirements(1215, "Dragon dagger"), lost_city);
+		assert_eq!(table.requirements(5698, "Dragon dagger(p++)"), lost_city);
+		assert!(table.requirements(1205, "Bronze dagger").is_empty());
+	}
+
+	#[test]
+	fn ids_and_names_both_apply() {
+		let table = table(r#"
+			[[item]]
+			id = 11838
+			unlocks = ["Saradomin's light"]
+
+			[[item]]
+			name = "Saradomin sword"
+			unlocks = ["Commander Zilyana"]
+		"#).unwrap();
+		assert_eq!(table.requirements(11838, "Saradomin sword"), vec![
+			Requirement::Unlock("Saradomin's light".into()),
+			Requirement::Unlock("Commander Zilyana".into()),
+		]);
+		assert_eq!(table.requirements(11839, "Saradomin sword"), vec![Requirement::Unlock("Commander Zilyana".into())]);
+	}
+
+	#[test]
+	fn finished_quests_unlock_without_regard_to_case() {
+		let table = UnlockTable::builtin();
+		let profile = Profile::default();
+		assert_eq!(profile.missing(&table, 1215, "Dragon dagger"), vec![Requirement::Quest("Lost City".into())]);
+		let profile = Profile { quests: std::iter::once("LOST CITY".to_string()).collect(), ..Profile::default() };
+		assert!(profile.missing(&table, 1215, "Dragon dagger").is_empty());
+		// a quest doesn't stand in for a diary of the same name
+		let profile = Profile { diaries: std::iter::once("Lost City".to_string()).collect(), ..Profile::default() };
+		assert!(!profile.missing(&table, 1215, "Dragon dagger").is_empty());
+	}
+
+	#[test]
+	fn entries_need_an_id_or_a_name() {
+		let error = table(r#"
+			[[item]]
+			name = "Dragon dagger"
+			quests = ["Lost City"]
+
+			[[item]]
+			quests = ["Heroes' Quest"]
+		"#).unwrap_err();
+		assert!(error.contains("#2"), "{}", error);
+	}
+}